@@ -0,0 +1,100 @@
+use std::{fs::{File, OpenOptions}, io::{self, BufRead, BufReader, Seek, SeekFrom, Write}, path::{Path, PathBuf}, sync::{Mutex, PoisonError}};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// Entries parsed so far, and how many bytes of the file they were parsed from
+struct ReadEntries<T> {
+  entries: Vec<T>,
+  len: u64,
+}
+
+// Append-only log file, one JSON entry per line. Entries are kept in memory once read, so reading the
+// log only parses the lines appended since the last read
+pub struct AppendLog<T> {
+  path: PathBuf,
+  file: Mutex<File>,
+  read: Mutex<ReadEntries<T>>,
+}
+
+impl<T: Serialize + DeserializeOwned> AppendLog<T> {
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    let path = path.as_ref().to_path_buf();
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    Ok(Self { path, file: Mutex::new(file), read: Mutex::new(ReadEntries { entries: Vec::new(), len: 0 }) })
+  }
+
+  pub fn append(&self, entry: &T) -> io::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    // Entries are written as whole lines, a panic while holding the lock leaves the file intact
+    let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+    writeln!(file, "{}", line)
+  }
+
+  // Calls `f` with all entries, oldest first
+  pub fn with_entries<R>(&self, f: impl FnOnce(&[T]) -> R) -> io::Result<R> {
+    // `len` always counts the bytes of the entries added, a panic in `f` leaves them consistent
+    let mut read = self.read.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut file = File::open(&self.path)?;
+    if file.metadata()?.len() < read.len {
+      // Truncated since the last read, e.g. rotated
+      read.entries.clear();
+      read.len = 0;
+    }
+    file.seek(SeekFrom::Start(read.len))?;
+
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    loop {
+      line.clear();
+      let num_bytes = reader.read_line(&mut line)?;
+      // The last line is still being written, it is read once complete
+      if num_bytes == 0 || !line.ends_with('\n') {
+        break;
+      }
+      read.len += num_bytes as u64;
+      // Skip lines which were only partially written, e.g. when the server was killed
+      if let Ok(entry) = serde_json::from_str::<T>(&line) {
+        read.entries.push(entry);
+      }
+    }
+
+    Ok(f(&read.entries))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn read(log: &AppendLog<Vec<u32>>) -> Vec<Vec<u32>> {
+    log.with_entries(|entries| entries.to_vec()).unwrap()
+  }
+
+  #[test]
+  fn reads_only_complete_entries_appended_since_the_last_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log.jsonl");
+    let log = AppendLog::open(&path).unwrap();
+
+    log.append(&vec![1]).unwrap();
+    assert_eq!(read(&log), [vec![1]]);
+
+    // An entry still being written is read once its line is complete
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    write!(file, "[2,").unwrap();
+    assert_eq!(read(&log), [vec![1]]);
+    writeln!(file, "3]").unwrap();
+    log.append(&vec![4]).unwrap();
+    assert_eq!(read(&log), [vec![1], vec![2, 3], vec![4]]);
+
+    // Lines which aren't entries are skipped
+    writeln!(file, "[5").unwrap();
+    log.append(&vec![6]).unwrap();
+    assert_eq!(read(&log), [vec![1], vec![2, 3], vec![4], vec![6]]);
+
+    // A truncated file is read again from the start
+    std::fs::write(&path, "[7]\n").unwrap();
+    assert_eq!(read(&log), [vec![7]]);
+  }
+}
@@ -0,0 +1,232 @@
+use std::{fmt, path::Path, sync::{Arc, PoisonError, RwLock}};
+
+use axum::{extract::{OriginalUri, Request, State}, http::header, middleware::Next, response::Response};
+use config::{API_KEYS_ENV, API_KEYS_PATH};
+use sha2::{Digest, Sha256};
+
+use crate::routes::ApiError;
+
+// What an API key is allowed to do on the admin routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+  // Triggering and cancelling crawls
+  Crawl,
+  // Deleting indexed pages
+  Delete,
+  // Reloading the API keys, synonyms and click-through priors
+  Config,
+}
+
+impl fmt::Display for Scope {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Scope::Crawl => write!(f, "crawl"),
+      Scope::Delete => write!(f, "delete"),
+      Scope::Config => write!(f, "config"),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct ApiKey {
+  name: String,
+  hash: [u8; 32],
+  scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+  keys: Vec<ApiKey>,
+}
+
+fn hash_key(key: &str) -> [u8; 32] {
+  Sha256::digest(key.as_bytes()).into()
+}
+
+// Compares every byte, so the time taken doesn't tell how much of a hash matched
+fn hashes_equal(a: &[u8; 32], b: &[u8; 32]) -> bool {
+  a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn parse_scopes(scopes: &str) -> Result<Vec<Scope>, String> {
+  let mut parsed = Vec::new();
+  for scope in scopes.split(',').map(str::trim) {
+    match scope {
+      "*" => parsed.extend([Scope::Crawl, Scope::Delete, Scope::Config]),
+      "crawl" => parsed.push(Scope::Crawl),
+      "delete" => parsed.push(Scope::Delete),
+      "config" => parsed.push(Scope::Config),
+      _ => return Err(format!("Unknown scope `{}`", scope)),
+    }
+  }
+  Ok(parsed)
+}
+
+impl ApiKeys {
+  // Keys from `config::API_KEYS_PATH` in `data_dir` and the `config::API_KEYS_ENV` environment variable.
+  // No keys are configured when neither exists, and every admin request is rejected
+  pub fn from_config(data_dir: &Path) -> Result<Self, String> {
+    let path = data_dir.join(API_KEYS_PATH);
+    let mut contents = match std::fs::read_to_string(&path) {
+      Ok(contents) => contents,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+      Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    if let Ok(keys) = std::env::var(API_KEYS_ENV) {
+      contents.push('\n');
+      contents.push_str(&keys.replace(';', "\n"));
+    }
+
+    Self::parse(&contents)
+  }
+
+  // Parses `name:sha256 hex:scopes` entries, one per line. `#` starts a comment
+  pub fn parse(contents: &str) -> Result<Self, String> {
+    let mut keys = Vec::new();
+    for line in contents.lines().map(str::trim) {
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let parts: Vec<&str> = line.splitn(3, ':').map(str::trim).collect();
+      let [name, hash, scopes] = parts[..] else {
+        return Err(format!("Invalid API key entry, expected `name:sha256:scopes`: {}", line));
+      };
+      let hash = hex::decode(hash).ok()
+        .and_then(|hash| <[u8; 32]>::try_from(hash).ok())
+        .ok_or_else(|| format!("Invalid SHA-256 hash of API key `{}`", name))?;
+
+      keys.push(ApiKey { name: name.to_string(), hash, scopes: parse_scopes(scopes)? });
+    }
+
+    Ok(Self { keys })
+  }
+
+  pub fn len(&self) -> usize {
+    self.keys.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.keys.is_empty()
+  }
+
+  fn find(&self, key: &str) -> Option<&ApiKey> {
+    let hash = hash_key(key);
+    self.keys.iter().find(|api_key| hashes_equal(&api_key.hash, &hash))
+  }
+
+  // Name of the configured key matching `key`
+  pub fn name_of(&self, key: &str) -> Option<&str> {
+    self.find(key).map(|api_key| api_key.name.as_str())
+  }
+}
+
+// Key sent with the request, as `Authorization: Bearer <key>` or `X-API-Key: <key>`
+pub fn request_key(request: &Request) -> Option<&str> {
+  let headers = request.headers();
+  headers.get(header::AUTHORIZATION)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.strip_prefix("Bearer "))
+    .or_else(|| headers.get("x-api-key").and_then(|value| value.to_str().ok()))
+    .map(str::trim)
+    .filter(|key| !key.is_empty())
+}
+
+// State of the `authorize` middleware, the scope required by the routes it is applied to
+#[derive(Clone)]
+pub struct RequireScope {
+  pub keys: Arc<RwLock<ApiKeys>>,
+  pub scope: Scope,
+}
+
+// Rejects requests without a known API key ( 401 ) or whose key lacks the required scope ( 403 )
+pub async fn authorize(State(required): State<RequireScope>, request: Request, next: Next) -> Result<Response, ApiError> {
+  let key = request_key(&request)
+    .ok_or_else(|| ApiError::Unauthorized("Missing API key".to_string()))?;
+
+  let name = {
+    let keys = required.keys.read().unwrap_or_else(PoisonError::into_inner);
+    let api_key = keys.find(key)
+      .ok_or_else(|| ApiError::Unauthorized("Invalid API key".to_string()))?;
+    if !api_key.scopes.contains(&required.scope) {
+      return Err(ApiError::Forbidden(format!("API key `{}` doesn't have the `{}` scope", api_key.name, required.scope)));
+    }
+    api_key.name.clone()
+  };
+
+  // Nested routes see the path without their prefix, the original one tells which version was used
+  let path = request.extensions().get::<OriginalUri>().map_or(request.uri().path(), |uri| uri.path());
+  tracing::info!(api_key = name, "{} {}", request.method(), path);
+  Ok(next.run(request).await)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::body::Body;
+
+  // SHA-256 of `secret`
+  const SECRET_HASH: &str = "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b";
+
+  fn request(headers: &[(&str, &str)]) -> Request {
+    let mut request = Request::builder();
+    for (name, value) in headers {
+      request = request.header(*name, *value);
+    }
+    request.body(Body::empty()).unwrap()
+  }
+
+  #[test]
+  fn keys_are_parsed_with_their_scopes() {
+    let keys = ApiKeys::parse(&format!("# Admin keys\n\n  ops : {} : crawl, delete\nroot:{}:*\n", SECRET_HASH, SECRET_HASH.to_uppercase())).unwrap();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys.keys[0].name, "ops");
+    assert_eq!(keys.keys[0].scopes, [Scope::Crawl, Scope::Delete]);
+    assert_eq!(keys.keys[1].scopes, [Scope::Crawl, Scope::Delete, Scope::Config]);
+    assert_eq!(keys.name_of("secret"), Some("ops"));
+    assert_eq!(keys.name_of("Secret"), None);
+
+    assert!(ApiKeys::parse("# No keys").unwrap().is_empty());
+  }
+
+  #[test]
+  fn invalid_keys_are_rejected() {
+    for (contents, error) in [
+      (format!("ops:{}", SECRET_HASH), "Invalid API key entry, expected `name:sha256:scopes`"),
+      ("ops:secret:crawl".to_string(), "Invalid SHA-256 hash of API key `ops`"),
+      (format!("ops:{}:crawl", &SECRET_HASH[2..]), "Invalid SHA-256 hash of API key `ops`"),
+      (format!("ops:{}:crawl,search", SECRET_HASH), "Unknown scope `search`"),
+      (format!("ops:{}:", SECRET_HASH), "Unknown scope ``"),
+    ] {
+      let e = ApiKeys::parse(&contents).unwrap_err();
+      assert!(e.starts_with(error), "{}: {}", contents, e);
+    }
+  }
+
+  #[test]
+  fn hashes_are_equal_only_when_every_byte_is() {
+    let hash = hash_key("secret");
+    assert_eq!(hex::encode(hash), SECRET_HASH);
+    assert!(hashes_equal(&hash, &hash_key("secret")));
+
+    for i in [0, 17, 31] {
+      let mut other = hash;
+      other[i] ^= 1;
+      assert!(!hashes_equal(&hash, &other), "{}", i);
+    }
+  }
+
+  #[test]
+  fn keys_are_sent_as_bearer_tokens_or_x_api_key() {
+    assert_eq!(request_key(&request(&[("authorization", "Bearer secret")])), Some("secret"));
+    assert_eq!(request_key(&request(&[("x-api-key", " secret ")])), Some("secret"));
+    // The bearer token comes first, other authorization schemes aren't keys
+    assert_eq!(request_key(&request(&[("authorization", "Bearer secret"), ("x-api-key", "other")])), Some("secret"));
+    assert_eq!(request_key(&request(&[("authorization", "Basic c2VjcmV0"), ("x-api-key", "other")])), Some("other"));
+
+    for headers in [&[][..], &[("authorization", "Basic c2VjcmV0")], &[("authorization", "Bearer ")], &[("x-api-key", "")]] {
+      assert_eq!(request_key(&request(headers)), None, "{:?}", headers);
+    }
+  }
+}
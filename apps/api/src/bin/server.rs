@@ -0,0 +1,122 @@
+use std::{net::{IpAddr, SocketAddr}, path::PathBuf};
+
+use api::{create_router, ApiState};
+use tokio::signal::unix::{signal, SignalKind};
+
+const USAGE: &str = "Usage: server [--bind <address>] [--port <port>] [--data <dir>] [--index <path>] [--workers <threads>] [--trusted-proxies <hops>]
+
+Options default to the BIND_ADDRESS, PORT, DATA_DIR, INDEX_PATH, WORKER_THREADS and TRUSTED_PROXIES
+environment variables, then to the defaults in the config crate. The data directory holds the API keys,
+synonyms, logs and click-through priors, and the index unless `--index` is given. 0 worker threads
+starts one per CPU core. Set
+`--trusted-proxies` to the number of proxies in front of the server which append to X-Forwarded-For,
+0 ( the default ) identifies rate limited clients by their connection address";
+
+#[derive(Debug)]
+struct ServerOptions {
+  bind_address: IpAddr,
+  port: u16,
+  data_dir: PathBuf,
+  // `None` serves the index in the data directory
+  index_path: Option<PathBuf>,
+  worker_threads: usize,
+  trusted_proxies: usize,
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+  value.parse().map_err(|_| format!("Invalid {}: {}", name, value))
+}
+
+impl ServerOptions {
+  // Flags override the environment variables, which override the defaults in `config`
+  fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+    let env = |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+    let mut bind_address = env("BIND_ADDRESS", config::SERVER_BIND_ADDRESS);
+    let mut port = env("PORT", &config::SERVER_PORT.to_string());
+    let mut data_dir = env("DATA_DIR", config::DATA_DIR);
+    let mut index_path = std::env::var("INDEX_PATH").ok();
+    let mut worker_threads = env("WORKER_THREADS", &config::SERVER_WORKER_THREADS.to_string());
+    let mut trusted_proxies = env("TRUSTED_PROXIES", &config::SERVER_TRUSTED_PROXIES.to_string());
+
+    while let Some(flag) = args.next() {
+      let option = match flag.as_str() {
+        "--bind" => &mut bind_address,
+        "--port" => &mut port,
+        "--data" => &mut data_dir,
+        "--index" => index_path.insert(String::new()),
+        "--workers" => &mut worker_threads,
+        "--trusted-proxies" => &mut trusted_proxies,
+        "--help" | "-h" => {
+          println!("{}", USAGE);
+          std::process::exit(0);
+        },
+        _ => return Err(format!("Unknown option `{}`", flag)),
+      };
+      *option = args.next().ok_or_else(|| format!("Missing value of `{}`", flag))?;
+    }
+
+    Ok(Self {
+      bind_address: parse_value("bind address", &bind_address)?,
+      port: parse_value("port", &port)?,
+      data_dir: PathBuf::from(data_dir),
+      index_path: index_path.map(PathBuf::from),
+      worker_threads: parse_value("number of worker threads", &worker_threads)?,
+      trusted_proxies: parse_value("number of trusted proxies", &trusted_proxies)?,
+    })
+  }
+}
+
+// Resolves on SIGTERM or Ctrl+C. The running crawl is cancelled right away, so it stops visiting
+// pages while the in-flight requests finish
+async fn shutdown_signal(state: ApiState) {
+  let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+  tokio::select! {
+    _ = terminate.recv() => {},
+    _ = tokio::signal::ctrl_c() => {},
+  }
+
+  tracing::info!("Shutting down, finishing in-flight requests");
+  state.cancel_crawl();
+}
+
+async fn serve(options: ServerOptions) -> anyhow::Result<()> {
+  let mut state = ApiState::new(options.data_dir)?.with_trusted_proxies(options.trusted_proxies);
+  if let Some(index_path) = options.index_path {
+    state = state.with_index_path(index_path);
+  }
+  let app = create_router(state.clone());
+
+  let listener = tokio::net::TcpListener::bind((options.bind_address, options.port)).await?;
+  tracing::info!("Listening on {}", listener.local_addr()?);
+
+  // Connection info identifies clients for rate limiting when requests aren't forwarded by a proxy
+  axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+    .with_graceful_shutdown(shutdown_signal(state.clone()))
+    .await?;
+
+  state.stop_crawl().await;
+  tracing::info!("Server stopped");
+  Ok(())
+}
+
+fn main() {
+  let options = match ServerOptions::parse(std::env::args().skip(1)) {
+    Ok(options) => options,
+    Err(e) => {
+      eprintln!("{}\n\n{}", e, USAGE);
+      std::process::exit(2);
+    },
+  };
+  tracing_subscriber::fmt().with_max_level(tracing::Level::INFO).init();
+
+  let mut runtime = tokio::runtime::Builder::new_multi_thread();
+  if options.worker_threads > 0 {
+    runtime.worker_threads(options.worker_threads);
+  }
+  let runtime = runtime.enable_all().build().expect("Failed to start the async runtime");
+
+  if let Err(e) = runtime.block_on(serve(options)) {
+    tracing::error!("Server failed: {:#}", e);
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,150 @@
+use std::{collections::{HashMap, HashSet}, fmt::Write};
+
+use crate::query_log::{ClickLogEntry, QueryLogEntry};
+
+// Number of expected clicks a url's priors start from. The more clicks were expected, the more its
+// observed clicks move its prior away from 1
+const PRIOR_STRENGTH: f64 = 5.0;
+// Priors are clamped so clicks alone can't bury or promote a page
+const MIN_PRIOR: f64 = 0.5;
+const MAX_PRIOR: f64 = 2.0;
+
+#[derive(Default)]
+struct ClickStats {
+  clicks: f64,
+  expected_clicks: f64,
+}
+
+impl ClickStats {
+  // Clicks over expected clicks, smoothed towards 1
+  fn prior(&self) -> f64 {
+    ((self.clicks + PRIOR_STRENGTH) / (self.expected_clicks + PRIOR_STRENGTH)).clamp(MIN_PRIOR, MAX_PRIOR)
+  }
+}
+
+// Computes the click-through priors of the logged searches, in the format read by `searcher::ClickPriors`.
+// Results at the top are clicked more regardless of their relevance, so a url's clicks are compared
+// to the clicks expected from the positions it was shown at
+pub fn compute_click_priors(searches: &[QueryLogEntry], clicks: &[ClickLogEntry]) -> String {
+  let searches_by_id: HashMap<&str, &QueryLogEntry> = searches.iter()
+    .filter(|search| !search.id.is_empty())
+    .map(|search| (search.id.as_str(), search))
+    .collect();
+
+  // A result clicked more than once for the same search counts once
+  let clicked: HashSet<(&str, &str)> = clicks.iter()
+    .filter(|click| searches_by_id.contains_key(click.query_id.as_str()))
+    .map(|click| (click.query_id.as_str(), click.url.as_str()))
+    .collect();
+
+  // Click-through rate per position, over all searches
+  let num_positions = searches.iter().map(|search| search.top_urls.len()).max().unwrap_or_default();
+  let mut impressions_per_position = vec![0.0; num_positions];
+  let mut clicks_per_position = vec![0.0; num_positions];
+  for search in searches_by_id.values() {
+    for (position, url) in search.top_urls.iter().enumerate() {
+      impressions_per_position[position] += 1.0;
+      if clicked.contains(&(search.id.as_str(), url.as_str())) {
+        clicks_per_position[position] += 1.0;
+      }
+    }
+  }
+  let ctr_per_position: Vec<f64> = clicks_per_position.iter().zip(&impressions_per_position)
+    .map(|(clicks, impressions)| if *impressions > 0.0 { clicks / impressions } else { 0.0 })
+    .collect();
+
+  let mut url_stats: HashMap<&str, ClickStats> = HashMap::new();
+  let mut query_url_stats: HashMap<(String, &str), ClickStats> = HashMap::new();
+  for search in searches_by_id.values() {
+    let query = searcher::normalize_query(&search.query);
+    for (position, url) in search.top_urls.iter().enumerate() {
+      let clicks = if clicked.contains(&(search.id.as_str(), url.as_str())) { 1.0 } else { 0.0 };
+      for stats in [url_stats.entry(url).or_default(), query_url_stats.entry((query.clone(), url)).or_default()] {
+        stats.clicks += clicks;
+        stats.expected_clicks += ctr_per_position[position];
+      }
+    }
+  }
+
+  let mut priors = String::from("# query\turl\tboost, an empty query applies to all queries\n");
+  for (url, stats) in url_stats.iter().filter(|(_, stats)| stats.clicks > 0.0 || stats.expected_clicks > 0.0) {
+    writeln!(priors, "\t{}\t{:.4}", url, stats.prior()).unwrap();
+  }
+  for ((query, url), stats) in query_url_stats.iter().filter(|(_, stats)| stats.clicks > 0.0 || stats.expected_clicks > 0.0) {
+    writeln!(priors, "{}\t{}\t{:.4}", query, url, stats.prior()).unwrap();
+  }
+
+  priors
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::query_log::QueryFilters;
+
+  fn search(id: &str, query: &str, top_urls: &[&str]) -> QueryLogEntry {
+    QueryLogEntry {
+      id: id.to_string(),
+      timestamp: 0,
+      query: query.to_string(),
+      filters: QueryFilters::default(),
+      total: top_urls.len(),
+      took_ms: 1.0,
+      top_urls: top_urls.iter().map(|url| url.to_string()).collect(),
+    }
+  }
+
+  fn click(query_id: &str, url: &str, position: usize) -> ClickLogEntry {
+    ClickLogEntry { timestamp: 0, query_id: query_id.to_string(), url: url.to_string(), position }
+  }
+
+  #[test]
+  fn priors_compare_clicks_to_the_clicks_expected_at_each_position() {
+    let searches = [
+      search("a", "Rust Vec", &["vec", "slice"]),
+      search("b", "rust  vec", &["vec", "slice"]),
+      search("c", "go", &["slice", "vec"]),
+      // Searches logged before they had ids can't be clicked
+      search("", "go", &["maps"]),
+    ];
+    let clicks = [
+      click("a", "vec", 1),
+      // Counted once per search
+      click("a", "vec", 1),
+      click("b", "slice", 2),
+      click("c", "vec", 2),
+      // Urls which weren't shown and unknown searches are ignored
+      click("a", "maps", 3),
+      click("z", "vec", 1),
+    ];
+
+    // Clicked 1 of 3 times at position 1 and 2 of 3 times at position 2. `vec` got 2 clicks where
+    // 1/3 + 1/3 + 2/3 were expected, smoothed with 5 expected clicks: (2 + 5) / (4/3 + 5)
+    let priors = compute_click_priors(&searches, &clicks);
+    let mut lines: Vec<&str> = priors.lines().collect();
+    assert!(lines.remove(0).starts_with('#'));
+    lines.sort();
+    assert_eq!(lines, [
+      "\tslice\t0.9000",
+      "\tvec\t1.1053",
+      "go\tslice\t0.9375",
+      "go\tvec\t1.0588",
+      "rust vec\tslice\t0.9474",
+      "rust vec\tvec\t1.0588",
+    ]);
+  }
+
+  #[test]
+  fn priors_are_clamped() {
+    // `liked` is clicked every time, `ignored` never, at a position clicked a quarter of the time
+    let searches: Vec<QueryLogEntry> = (0..400)
+      .map(|i| search(&i.to_string(), "q", &[if i < 100 { "liked" } else { "ignored" }]))
+      .collect();
+    let clicks: Vec<ClickLogEntry> = (0..100).map(|i| click(&i.to_string(), "liked", 1)).collect();
+
+    let priors = compute_click_priors(&searches, &clicks);
+    for expected in ["\tliked\t2.0000", "\tignored\t0.5000", "q\tliked\t2.0000", "q\tignored\t0.5000"] {
+      assert!(priors.lines().any(|line| line == expected), "{}", expected);
+    }
+  }
+}
@@ -1,33 +1,285 @@
-use std::vec;
+use std::{path::PathBuf, sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock}, vec};
 
-use axum::{routing::{get, post}, Router};
+use anyhow::Context;
+use axum::{middleware, routing::{delete, get, post}, Router};
 use axum::http::Method;
 use routes::health_check::health_check;
 use routes::trigger_indexing::trigger_indexing;
 use routes::search::search;
 use routes::pages_per_site::pages_per_site;
 use routes::scraped_urls::scraped_urls;
-use tower_http::cors::CorsLayer;
+use routes::query_analytics::{latency, query_volume, top_queries, zero_result_queries};
+use routes::clicks::{click, go};
+use routes::stats::stats;
+use routes::openapi::openapi;
+use routes::admin::{cancel_indexing, delete_documents, reload_config};
+use schema::SnapshotStore;
+use searcher::{ClickPriors, DocSearcher};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tower_http::{catch_panic::CatchPanicLayer, cors::CorsLayer, request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer}};
 
-use crate::routes;
+use crate::{auth::{authorize, ApiKeys, RequireScope, Scope}, click_priors::compute_click_priors, deprecation::deprecated_alias, error_body::error_body, query_log::{ClickLog, QueryLog}, rate_limit::{rate_limit, RateLimiter}, routes::{self, ApiError}};
+
+// Searcher with the directory of the snapshot it reads
+type OpenedSearcher = (PathBuf, Arc<DocSearcher>);
 
 #[derive(Clone)]
 pub struct ApiState {
+  // Directory of the API's config files, logs and click-through priors, see `config::DATA_DIR`
+  pub data_dir: PathBuf,
+  // Index directory the crawler publishes snapshots into, see `schema::SnapshotStore`
+  pub index_path: PathBuf,
+  pub query_log: Arc<QueryLog>,
+  pub click_log: Arc<ClickLog>,
+  // Shared by all requests for its result cache. Opened on first use, the index doesn't exist before the first crawl
+  searcher: Arc<Mutex<Option<OpenedSearcher>>>,
+  // Cancels the running crawl, `None` when no crawl is running
+  crawl: Arc<Mutex<Option<CancellationToken>>>,
+  // Notified when the running crawl finished
+  crawl_finished: Arc<Notify>,
+  api_keys: Arc<RwLock<ApiKeys>>,
+  // Proxies trusted to set `X-Forwarded-For`, see `config::RATE_LIMIT_TRUSTED_PROXIES`
+  trusted_proxies: usize,
+}
+
+impl ApiState {
+  // State of an API keeping its config files and logs in `data_dir`, and serving the index in its
+  // `config::INDEX_PATH`. Fails when the API keys can't be read or the logs can't be opened for appending
+  pub fn new(data_dir: PathBuf) -> anyhow::Result<Self> {
+    let api_keys = ApiKeys::from_config(&data_dir).map_err(|e| anyhow::anyhow!("Invalid API keys: {}", e))?;
+    if api_keys.is_empty() {
+      tracing::warn!("No API keys configured, admin routes will reject all requests");
+    }
+    let query_log_path = data_dir.join(config::QUERY_LOG_PATH);
+    let query_log = QueryLog::open(&query_log_path)
+      .with_context(|| format!("Failed to open the query log {}", query_log_path.display()))?;
+    let click_log_path = data_dir.join(config::CLICK_LOG_PATH);
+    let click_log = ClickLog::open(&click_log_path)
+      .with_context(|| format!("Failed to open the click log {}", click_log_path.display()))?;
+
+    Ok(Self {
+      index_path: data_dir.join(config::INDEX_PATH),
+      data_dir,
+      query_log: Arc::new(query_log),
+      click_log: Arc::new(click_log),
+      searcher: Arc::new(Mutex::new(None)),
+      crawl: Arc::new(Mutex::new(None)),
+      crawl_finished: Arc::new(Notify::new()),
+      api_keys: Arc::new(RwLock::new(api_keys)),
+      trusted_proxies: config::RATE_LIMIT_TRUSTED_PROXIES,
+    })
+  }
+
+  // Serves the index at `index_path` instead of the one in the data directory
+  pub fn with_index_path(mut self, index_path: PathBuf) -> Self {
+    self.index_path = index_path;
+    self
+  }
+
+  pub fn with_trusted_proxies(mut self, trusted_proxies: usize) -> Self {
+    self.trusted_proxies = trusted_proxies;
+    self
+  }
+
+  // Nothing is left half updated by a panic while holding the lock, the searcher is either opened or not
+  fn lock_searcher(&self) -> MutexGuard<'_, Option<OpenedSearcher>> {
+    self.searcher.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  // Directory of the snapshot currently published in `index_path`
+  pub fn current_snapshot(&self) -> Result<PathBuf, ApiError> {
+    SnapshotStore::new(&self.index_path).current_path()
+      .map_err(|e| ApiError::IndexUnavailable(format!("Failed to read the current index snapshot: {}", e)))
+  }
+
+  pub fn searcher(&self) -> Result<Arc<DocSearcher>, ApiError> {
+    let mut searcher = self.lock_searcher();
+    match searcher.as_ref() {
+      Some((_, searcher)) => Ok(searcher.clone()),
+      None => {
+        let snapshot = self.current_snapshot()?;
+        let doc_searcher = Arc::new(DocSearcher::with_data_dir(snapshot.to_string_lossy().into_owned(), &self.data_dir)?);
+        *searcher = Some((snapshot, doc_searcher.clone()));
+        Ok(doc_searcher)
+      },
+    }
+  }
+
+  // The searcher if it was already opened, for updates which can wait until it is
+  fn opened_searcher(&self) -> Option<Arc<DocSearcher>> {
+    self.lock_searcher().as_ref().map(|(_, searcher)| searcher.clone())
+  }
+
+  // Drops the searcher when another snapshot was published ( or rolled back to ) since it was opened,
+  // the next search opens the current one. Requests still running keep the snapshot they started with
+  pub fn refresh_snapshot(&self) {
+    let snapshot = match self.current_snapshot() {
+      Ok(snapshot) => snapshot,
+      Err(e) => {
+        tracing::warn!("{}", e);
+        return;
+      },
+    };
+
+    let mut searcher = self.lock_searcher();
+    if searcher.as_ref().is_some_and(|(opened, _)| *opened != snapshot) {
+      *searcher = None;
+      tracing::info!("Switched to index snapshot {}", snapshot.display());
+    }
+  }
+
+  // Replaces the API keys and drops the searcher, which is reopened with the current config by the next search
+  pub fn reload(&self, api_keys: ApiKeys) {
+    *self.api_keys.write().unwrap_or_else(PoisonError::into_inner) = api_keys;
+    *self.lock_searcher() = None;
+  }
+
+  fn lock_crawl(&self) -> MutexGuard<'_, Option<CancellationToken>> {
+    self.crawl.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  // Registers a new crawl, only one crawl can run at a time
+  pub fn start_crawl(&self) -> Result<CancellationToken, ApiError> {
+    let mut crawl = self.lock_crawl();
+    if crawl.is_some() {
+      return Err(ApiError::Conflict("A crawl is already running".to_string()));
+    }
+
+    let cancel = CancellationToken::new();
+    *crawl = Some(cancel.clone());
+    Ok(cancel)
+  }
+
+  pub fn finish_crawl(&self) {
+    *self.lock_crawl() = None;
+    self.crawl_finished.notify_waiters();
+  }
+
+  pub fn is_crawling(&self) -> bool {
+    self.lock_crawl().is_some()
+  }
+
+  // Returns false when no crawl is running
+  pub fn cancel_crawl(&self) -> bool {
+    match self.lock_crawl().as_ref() {
+      Some(cancel) => {
+        cancel.cancel();
+        true
+      },
+      None => false,
+    }
+  }
+
+  // Cancels the running crawl and waits until it finished, the pages indexed so far are committed
+  pub async fn stop_crawl(&self) {
+    let finished = self.crawl_finished.notified();
+    tokio::pin!(finished);
+    // Registered before cancelling, so a crawl finishing in between isn't missed
+    finished.as_mut().enable();
+    if self.cancel_crawl() {
+      tracing::info!("Waiting for the running crawl to finish");
+      finished.await;
+    }
+  }
+}
+
+// Recomputes the click-through priors used by the searcher every `config::CLICK_PRIORS_INTERVAL_SECS`
+fn spawn_click_priors_job(state: ApiState) {
+  tokio::spawn(async move {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(config::CLICK_PRIORS_INTERVAL_SECS));
+    loop {
+      interval.tick().await;
+
+      let state = state.clone();
+      let result = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+        let priors = state.query_log.with_entries(|searches| state.click_log.with_entries(|clicks| compute_click_priors(searches, clicks)))??;
+        std::fs::write(state.data_dir.join(config::CLICK_PRIORS_PATH), &priors)?;
+        if let Some(searcher) = state.opened_searcher() {
+          searcher.set_click_priors(ClickPriors::parse(&priors));
+        }
+        Ok(())
+      }).await;
+
+      match result {
+        Ok(Ok(())) => tracing::info!("Updated click-through priors"),
+        Ok(Err(e)) => tracing::warn!("Failed to update click-through priors: {}", e),
+        Err(e) => tracing::warn!("Click-through priors job panicked: {}", e),
+      }
+    }
+  });
+}
+
+// Picks up snapshots published by crawls outside the API ( the CLI ) and rollbacks, every
+// `config::SNAPSHOT_POLL_INTERVAL_SECS`
+fn spawn_snapshot_watch_job(state: ApiState) {
+  tokio::spawn(async move {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(config::SNAPSHOT_POLL_INTERVAL_SECS));
+    loop {
+      interval.tick().await;
+
+      let state = state.clone();
+      if let Err(e) = tokio::task::spawn_blocking(move || state.refresh_snapshot()).await {
+        tracing::warn!("Snapshot watch job panicked: {}", e);
+      }
+    }
+  });
+}
+
+// Routes which require an API key with `scope`
+fn admin_routes(router: Router<ApiState>, state: &ApiState, scope: Scope) -> Router<ApiState> {
+  let required = RequireScope { keys: state.api_keys.clone(), scope };
+  router.route_layer(middleware::from_fn_with_state(required, authorize))
+}
+
+// Routes of version 1 of the API, relative to where they are nested
+fn v1_routes(state: &ApiState) -> Router<ApiState> {
+  let crawl_routes = Router::new()
+    .route("/index/trigger", post(trigger_indexing))
+    .route("/index/cancel", post(cancel_indexing));
+  let delete_routes = Router::new()
+    .route("/documents", delete(delete_documents));
+  let config_routes = Router::new()
+    .route("/config/reload", post(reload_config));
+
+  Router::new()
+    .route("/health", get(health_check))
+    .route("/search", get(search))
+    .route("/click", post(click))
+    .route("/go", get(go))
+    .route("/analytics", get(pages_per_site))
+    .route("/analytics/queries/top", get(top_queries))
+    .route("/analytics/queries/zero_results", get(zero_result_queries))
+    .route("/analytics/latency", get(latency))
+    .route("/analytics/volume", get(query_volume))
+    .route("/crawled_urls", get(scraped_urls))
+    .route("/stats", get(stats))
+    .route("/openapi.json", get(openapi))
+    .merge(admin_routes(crawl_routes, state, Scope::Crawl))
+    .merge(admin_routes(delete_routes, state, Scope::Delete))
+    .merge(admin_routes(config_routes, state, Scope::Config))
 }
 
-pub fn create_router() -> Router {
-  let state = ApiState {};
+// Routes are served under `/api/v1`, and under `/api` as deprecated aliases of the same version
+pub fn create_router(state: ApiState) -> Router {
+  spawn_click_priors_job(state.clone());
+  spawn_snapshot_watch_job(state.clone());
 
   Router::new()
-    .route("/api/health", get(health_check))
-    .route("/api/index/trigger", post(trigger_indexing))
-    .route("/api/search", get(search))
-    .route("/api/analytics", get(pages_per_site))
-    .route("/api/crawled_urls", get(scraped_urls))
+    .nest("/api/v1", v1_routes(&state))
+    .nest("/api", v1_routes(&state).layer(middleware::from_fn(deprecated_alias)))
+    .fallback(routes::not_found)
+    .with_state(state.clone())
+    .layer(CatchPanicLayer::new())
+    .layer(middleware::from_fn_with_state(RateLimiter::new(state.api_keys.clone(), state.trusted_proxies), rate_limit))
+    .layer(middleware::from_fn(error_body))
+    .layer(PropagateRequestIdLayer::x_request_id())
+    .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
     .layer(
       CorsLayer::new()
         .allow_origin(tower_http::cors::Any)
-        .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers(vec![axum::http::header::CONTENT_TYPE])
+        .allow_methods(vec![Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+        .allow_headers(vec![axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION, axum::http::HeaderName::from_static("x-api-key")])
+        .expose_headers(vec![axum::http::HeaderName::from_static("x-request-id"), axum::http::HeaderName::from_static("deprecation"), axum::http::header::LINK])
     ) 
 }
\ No newline at end of file
@@ -0,0 +1,15 @@
+use axum::{extract::Request, http::{header, HeaderValue}, middleware::Next, response::Response};
+
+// Marks responses of the unversioned `/api` aliases as deprecated ( `Deprecation` header ) and links
+// the `/api/v1` route replacing them. Nested under `/api`, the request path doesn't have the prefix
+pub async fn deprecated_alias(request: Request, next: Next) -> Response {
+  let successor = format!("</api/v1{}>; rel=\"successor-version\"", request.uri().path());
+
+  let mut response = next.run(request).await;
+  let headers = response.headers_mut();
+  headers.insert("deprecation", HeaderValue::from_static("true"));
+  if let Ok(successor) = HeaderValue::from_str(&successor) {
+    headers.insert(header::LINK, successor);
+  }
+  response
+}
@@ -0,0 +1,64 @@
+use axum::{body::Body, extract::Request, http::{header, HeaderValue, StatusCode}, middleware::Next, response::Response};
+use tower_http::request_id::RequestId;
+
+use crate::routes::ErrorInfo;
+
+// Plain text error bodies of axum's extractors are short, anything longer isn't worth reading
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+fn code_for_status(status: StatusCode) -> &'static str {
+  match status {
+    StatusCode::BAD_REQUEST => "invalid_parameter",
+    StatusCode::NOT_FOUND => "not_found",
+    StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+    StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+    StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+    StatusCode::UNPROCESSABLE_ENTITY => "invalid_body",
+    status if status.is_server_error() => "internal_error",
+    _ => "bad_request",
+  }
+}
+
+// Adds the request id ( set by `SetRequestIdLayer` ) to the body of every error response. Errors
+// which weren't returned as an `ApiError`, like rejections of axum's extractors, unknown routes and
+// panics, are turned into the same body
+pub async fn error_body(request: Request, next: Next) -> Response {
+  let request_id = request.extensions().get::<RequestId>()
+    .and_then(|id| id.header_value().to_str().ok())
+    .map(|id| id.to_string());
+
+  let response = next.run(request).await;
+  let status = response.status();
+  if !status.is_client_error() && !status.is_server_error() {
+    return response;
+  }
+
+  let (mut parts, body) = response.into_parts();
+  let info = match parts.extensions.remove::<ErrorInfo>() {
+    Some(info) => info,
+    None => {
+      let text = axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES).await.ok()
+        .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+        .filter(|text| !text.trim().is_empty());
+      let reason = status.canonical_reason().unwrap_or("Error").to_string();
+
+      // Server errors can carry panic messages, which are logged instead of sent to the client
+      if status.is_server_error() {
+        ErrorInfo { code: code_for_status(status), message: reason, cause: text }
+      } else {
+        ErrorInfo { code: code_for_status(status), message: text.unwrap_or(reason), cause: None }
+      }
+    },
+  };
+
+  if status.is_server_error() {
+    tracing::error!(
+      request_id = request_id.as_deref().unwrap_or_default(),
+      "{}: {}", info.message, info.cause.as_deref().unwrap_or_default()
+    );
+  }
+
+  parts.headers.remove(header::CONTENT_LENGTH);
+  parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+  Response::from_parts(parts, Body::from(serde_json::to_vec(&info.body(request_id.as_deref())).unwrap_or_default()))
+}
@@ -0,0 +1,13 @@
+// The API, shared by the Shuttle entry point ( `main.rs` ) and the standalone server ( `bin/server.rs` )
+mod append_log;
+mod auth;
+mod click_priors;
+mod create_routes;
+mod deprecation;
+mod error_body;
+mod openapi;
+mod query_log;
+mod rate_limit;
+mod routes;
+
+pub use create_routes::{create_router, ApiState};
@@ -0,0 +1,183 @@
+use api_types::{ApiObject, ApiSchema};
+use api_types::v1::{
+  AnalyticsQuery, ClickRequest, DeleteDocumentsQuery, DeleteDocumentsResponse, ErrorResponse, GoQuery, LatencyPercentiles,
+  MessageResponse, PagesPerSiteEntry, QueryCount, ReloadConfigResponse, ScrapedUrlsQuery, ScrapedUrlsResponse, SearchQuery, SearchResponse,
+  StatsResponse, VolumeBucket,
+};
+use serde_json::{json, Map, Value};
+
+use crate::auth::Scope;
+
+// Operation of the OpenAPI document, the schemas come from the request and response types of the route
+struct Endpoint {
+  method: &'static str,
+  // Relative to `/api/v1`
+  path: &'static str,
+  summary: &'static str,
+  parameters: Vec<Value>,
+  request_body: Option<Value>,
+  // ( status, description, schema of the JSON body )
+  response: (u16, &'static str, Option<Value>),
+  scope: Option<Scope>,
+  // Schemas of the named types used by the operation
+  components: Map<String, Value>,
+}
+
+impl Endpoint {
+  fn new(method: &'static str, path: &'static str, summary: &'static str) -> Self {
+    Self {
+      method,
+      path,
+      summary,
+      parameters: Vec::new(),
+      request_body: None,
+      response: (200, "OK", None),
+      scope: None,
+      components: Map::new(),
+    }
+  }
+
+  fn query<T: ApiObject>(mut self) -> Self {
+    T::register(&mut self.components);
+    self.parameters = T::fields().into_iter()
+      .map(|field| json!({
+        "name": field.name,
+        "in": "query",
+        "required": field.required,
+        "schema": field.schema,
+      }))
+      .collect();
+    self
+  }
+
+  fn body<T: ApiSchema>(mut self) -> Self {
+    T::register(&mut self.components);
+    self.request_body = Some(json!({
+      "required": true,
+      "content": { "application/json": { "schema": T::schema() } },
+    }));
+    self
+  }
+
+  fn returns<T: ApiSchema>(mut self) -> Self {
+    T::register(&mut self.components);
+    self.response = (200, "OK", Some(T::schema()));
+    self
+  }
+
+  fn status(mut self, status: u16, description: &'static str) -> Self {
+    self.response = (status, description, None);
+    self
+  }
+
+  fn scope(mut self, scope: Scope) -> Self {
+    self.scope = Some(scope);
+    self
+  }
+
+  fn operation(&self) -> Value {
+    let (status, description, schema) = &self.response;
+    let mut response = json!({ "description": description });
+    if let Some(schema) = schema {
+      response["content"] = json!({ "application/json": { "schema": schema } });
+    }
+
+    let mut operation = json!({
+      "summary": self.summary,
+      "parameters": self.parameters,
+      "responses": {
+        status.to_string(): response,
+        "default": {
+          "description": "Error, see `code` for the kind of error",
+          "content": { "application/json": { "schema": ErrorResponse::schema() } },
+        },
+      },
+    });
+    if let Some(request_body) = &self.request_body {
+      operation["requestBody"] = request_body.clone();
+    }
+    if let Some(scope) = self.scope {
+      operation["description"] = json!(format!("Requires an API key with the `{}` scope", scope));
+      operation["security"] = json!([{ "bearerAuth": [] }, { "apiKey": [] }]);
+    }
+    operation
+  }
+}
+
+fn endpoints() -> Vec<Endpoint> {
+  vec![
+    Endpoint::new("get", "/health", "Health check"),
+    Endpoint::new("get", "/search", "Search the indexed documentation")
+      .query::<SearchQuery>()
+      .returns::<SearchResponse>(),
+    Endpoint::new("post", "/click", "Record a click on a search result")
+      .body::<ClickRequest>()
+      .status(204, "Click recorded"),
+    Endpoint::new("get", "/go", "Record a click on a search result and redirect to it")
+      .query::<GoQuery>()
+      .status(303, "Redirect to the result"),
+    Endpoint::new("get", "/analytics", "Number of indexed pages per site")
+      .returns::<Vec<PagesPerSiteEntry>>(),
+    Endpoint::new("get", "/analytics/queries/top", "Most searched queries")
+      .query::<AnalyticsQuery>()
+      .returns::<Vec<QueryCount>>(),
+    Endpoint::new("get", "/analytics/queries/zero_results", "Most searched queries without results")
+      .query::<AnalyticsQuery>()
+      .returns::<Vec<QueryCount>>(),
+    Endpoint::new("get", "/analytics/latency", "Search latency percentiles in milliseconds")
+      .query::<AnalyticsQuery>()
+      .returns::<LatencyPercentiles>(),
+    Endpoint::new("get", "/analytics/volume", "Number of searches per hour or day")
+      .query::<AnalyticsQuery>()
+      .returns::<Vec<VolumeBucket>>(),
+    Endpoint::new("get", "/crawled_urls", "Indexed urls, optionally of one domain")
+      .query::<ScrapedUrlsQuery>()
+      .returns::<ScrapedUrlsResponse>(),
+    Endpoint::new("get", "/stats", "Index size and result cache statistics")
+      .returns::<StatsResponse>(),
+    Endpoint::new("get", "/openapi.json", "This document"),
+    Endpoint::new("post", "/index/trigger", "Start a crawl")
+      .returns::<MessageResponse>()
+      .scope(Scope::Crawl),
+    Endpoint::new("post", "/index/cancel", "Cancel the running crawl")
+      .returns::<MessageResponse>()
+      .scope(Scope::Crawl),
+    Endpoint::new("delete", "/documents", "Delete indexed pages by url or domain")
+      .query::<DeleteDocumentsQuery>()
+      .returns::<DeleteDocumentsResponse>()
+      .scope(Scope::Delete),
+    Endpoint::new("post", "/config/reload", "Reload the API keys, synonyms and click-through priors")
+      .returns::<ReloadConfigResponse>()
+      .scope(Scope::Config),
+  ]
+}
+
+// OpenAPI 3 document of the `/api/v1` routes registered in `create_router`
+pub fn openapi_document() -> Value {
+  let mut components = Map::new();
+  ErrorResponse::register(&mut components);
+
+  let mut paths = Map::new();
+  for endpoint in endpoints() {
+    components.extend(endpoint.components.clone());
+    let path = paths.entry(format!("/api/v1{}", endpoint.path)).or_insert_with(|| json!({}));
+    path[endpoint.method] = endpoint.operation();
+  }
+
+  json!({
+    "openapi": "3.0.3",
+    "info": {
+      "title": "Mini Search API",
+      "version": "1",
+      "description": "The unversioned `/api/*` routes are deprecated aliases of the `/api/v1/*` routes",
+    },
+    "paths": paths,
+    "components": {
+      "schemas": components,
+      "securitySchemes": {
+        "bearerAuth": { "type": "http", "scheme": "bearer" },
+        "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" },
+      },
+    },
+  })
+}
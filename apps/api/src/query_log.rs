@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::append_log::AppendLog;
+
+// Number of result urls kept per search, the impressions of the click-through priors
+pub const LOGGED_RESULTS: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryFilters {
+  pub lang: Option<String>,
+  pub version: Option<String>,
+  pub sort: Option<String>,
+  pub after: Option<String>,
+  pub before: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryLogEntry {
+  // Query id returned with the results, referenced by clicks
+  #[serde(default)]
+  pub id: String,
+  // Epoch milliseconds
+  pub timestamp: i64,
+  pub query: String,
+  pub filters: QueryFilters,
+  pub total: usize,
+  pub took_ms: f64,
+  pub top_urls: Vec<String>,
+}
+
+// Searches served by the API, see `config::QUERY_LOG_PATH`
+pub type QueryLog = AppendLog<QueryLogEntry>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClickLogEntry {
+  // Epoch milliseconds
+  pub timestamp: i64,
+  pub query_id: String,
+  pub url: String,
+  // 1 based position of the result on the results page
+  pub position: usize,
+}
+
+// Results clicked from the search page, see `config::CLICK_LOG_PATH`
+pub type ClickLog = AppendLog<ClickLogEntry>;
@@ -0,0 +1,204 @@
+use std::{collections::HashMap, net::SocketAddr, sync::{Arc, Mutex, PoisonError, RwLock}, time::Instant};
+
+use axum::{extract::{ConnectInfo, Request, State}, middleware::Next, response::Response};
+use config::{ADMIN_RATE_LIMIT, SEARCH_RATE_LIMIT};
+
+use crate::{auth::{request_key, ApiKeys}, routes::ApiError};
+
+// Buckets are pruned above this many clients, so clients sending one request don't pile up
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum LimitClass {
+  Search,
+  Admin,
+}
+
+impl LimitClass {
+  // Routes without a class ( health, analytics, unknown routes ) aren't limited. Result clicks count
+  // as searches, so they can't be flooded to skew the click-through priors. The `/api` aliases share
+  // the limits of their `/api/v1` routes
+  fn for_path(path: &str) -> Option<Self> {
+    let route = path.strip_prefix("/api/v1").or_else(|| path.strip_prefix("/api"))?;
+    if route.starts_with("/search") || route.starts_with("/click") || route.starts_with("/go") {
+      Some(LimitClass::Search)
+    } else if route.starts_with("/index/") || route.starts_with("/documents") || route.starts_with("/config/") {
+      Some(LimitClass::Admin)
+    } else {
+      None
+    }
+  }
+
+  // ( tokens added per second, bucket size )
+  fn limit(&self) -> Option<(f64, f64)> {
+    let (per_minute, burst) = match self {
+      LimitClass::Search => SEARCH_RATE_LIMIT,
+      LimitClass::Admin => ADMIN_RATE_LIMIT,
+    };
+    (per_minute > 0).then(|| (per_minute as f64 / 60.0, burst.max(1) as f64))
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+  tokens: f64,
+  updated_at: Instant,
+}
+
+impl Bucket {
+  fn refill(&mut self, now: Instant, rate: f64, size: f64) {
+    let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * rate).min(size);
+    self.updated_at = now;
+  }
+
+  // Takes a token, or returns the seconds until one is available
+  fn take(&mut self, now: Instant, rate: f64, size: f64) -> Result<(), u64> {
+    self.refill(now, rate, size);
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      Ok(())
+    } else {
+      Err(((1.0 - self.tokens) / rate).ceil() as u64)
+    }
+  }
+}
+
+// Token buckets per client and route class
+#[derive(Clone)]
+pub struct RateLimiter {
+  buckets: Arc<Mutex<HashMap<(LimitClass, String), Bucket>>>,
+  api_keys: Arc<RwLock<ApiKeys>>,
+  // Proxies in front of the API which append the address they received the request from to
+  // `X-Forwarded-For`. 0 ignores the header, any client could send it
+  trusted_proxies: usize,
+}
+
+impl RateLimiter {
+  pub fn new(api_keys: Arc<RwLock<ApiKeys>>, trusted_proxies: usize) -> Self {
+    Self { buckets: Arc::new(Mutex::new(HashMap::new())), api_keys, trusted_proxies }
+  }
+
+  // Clients are identified by their API key when it is a configured one, so an invalid key can't be
+  // used to get a fresh bucket, and by their IP address otherwise. Behind proxies that is the address
+  // added by the outermost trusted proxy, the entries before it are sent by the client
+  fn client(&self, request: &Request) -> String {
+    let api_key = request_key(request).and_then(|key| {
+      let api_keys = self.api_keys.read().unwrap_or_else(PoisonError::into_inner);
+      api_keys.name_of(key).map(|name| format!("key:{}", name))
+    });
+    if let Some(api_key) = api_key {
+      return api_key;
+    }
+
+    let forwarded_for = request.headers().get("x-forwarded-for")
+      .filter(|_| self.trusted_proxies > 0)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.rsplit(',').nth(self.trusted_proxies - 1))
+      .map(|ip| ip.trim().to_string())
+      .filter(|ip| !ip.is_empty());
+    let connected_from = || request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip().to_string());
+
+    format!("ip:{}", forwarded_for.or_else(connected_from).unwrap_or_default())
+  }
+
+  // Takes a token from the client's bucket, or returns the seconds until one is available
+  fn acquire(&self, class: LimitClass, client: String, rate: f64, size: f64) -> Result<(), u64> {
+    let now = Instant::now();
+    let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+    if buckets.len() >= MAX_TRACKED_CLIENTS {
+      // Full buckets are the same as new ones
+      buckets.retain(|(class, _), bucket| match class.limit() {
+        Some((rate, size)) => {
+          bucket.refill(now, rate, size);
+          bucket.tokens < size
+        },
+        None => false,
+      });
+    }
+
+    buckets.entry((class, client))
+      .or_insert(Bucket { tokens: size, updated_at: now })
+      .take(now, rate, size)
+  }
+}
+
+// Rejects requests of clients over their route class' limit with 429 and `Retry-After`
+pub async fn rate_limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Result<Response, ApiError> {
+  let Some(class) = LimitClass::for_path(request.uri().path()) else {
+    return Ok(next.run(request).await);
+  };
+  let Some((rate, size)) = class.limit() else {
+    return Ok(next.run(request).await);
+  };
+
+  let client = limiter.client(&request);
+  limiter.acquire(class, client, rate, size)
+    .map_err(|retry_after_secs| ApiError::RateLimited { retry_after_secs })?;
+  Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+  use tower::ServiceExt;
+
+  use super::*;
+
+  fn request(forwarded_for: &str) -> Request {
+    Request::builder().uri("/api/v1/search").header("x-forwarded-for", forwarded_for).body(Body::empty()).unwrap()
+  }
+
+  #[test]
+  fn buckets_allow_a_burst_then_refill_at_the_rate() {
+    let start = Instant::now();
+    let mut bucket = Bucket { tokens: 3.0, updated_at: start };
+
+    for _ in 0..3 {
+      assert_eq!(bucket.take(start, 0.5, 3.0), Ok(()));
+    }
+    assert_eq!(bucket.take(start, 0.5, 3.0), Err(2));
+    assert_eq!(bucket.take(start + Duration::from_secs(1), 0.5, 3.0), Err(1));
+    assert_eq!(bucket.take(start + Duration::from_secs(2), 0.5, 3.0), Ok(()));
+
+    // Idle clients don't save up more than a burst
+    let later = start + Duration::from_secs(3600);
+    bucket.refill(later, 0.5, 3.0);
+    assert_eq!(bucket.tokens, 3.0);
+  }
+
+  #[test]
+  fn clients_are_the_address_added_by_the_outermost_trusted_proxy() {
+    let api_keys = Arc::new(RwLock::new(ApiKeys::default()));
+    let client = |trusted_proxies, forwarded_for| RateLimiter::new(api_keys.clone(), trusted_proxies).client(&request(forwarded_for));
+
+    assert_eq!(client(0, "10.0.0.1"), "ip:");
+    assert_eq!(client(1, "spoofed, 10.0.0.1"), "ip:10.0.0.1");
+    assert_eq!(client(2, "spoofed, 10.0.0.1, 10.0.0.2"), "ip:10.0.0.1");
+    // Fewer entries than proxies, the request didn't come through all of them
+    assert_eq!(client(2, "10.0.0.1"), "ip:");
+  }
+
+  #[tokio::test]
+  async fn clients_over_the_limit_are_told_when_to_retry() {
+    let limiter = RateLimiter::new(Arc::new(RwLock::new(ApiKeys::default())), 1);
+    let app = Router::new()
+      .route("/api/v1/search", get(|| async { "results" }))
+      .layer(middleware::from_fn_with_state(limiter, rate_limit));
+    let (_, burst) = SEARCH_RATE_LIMIT;
+
+    for _ in 0..burst {
+      let response = app.clone().oneshot(request("10.0.0.1")).await.unwrap();
+      assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.clone().oneshot(request("10.0.0.1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "1");
+
+    // Other clients have their own bucket, whatever they send before the proxy's entry
+    let response = app.oneshot(request("10.0.0.1, 10.0.0.2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+  }
+}
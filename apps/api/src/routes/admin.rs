@@ -0,0 +1,51 @@
+use api_types::v1::{DeleteDocumentsQuery, DeleteDocumentsResponse, MessageResponse, ReloadConfigResponse};
+use axum::{extract::{Query, State}, Json};
+use crawler::DocumentSelector;
+
+use crate::{auth::ApiKeys, create_routes::ApiState};
+use super::ApiError;
+
+pub async fn cancel_indexing(State(state): State<ApiState>) -> Result<Json<MessageResponse>, ApiError> {
+  if !state.cancel_crawl() {
+    return Err(ApiError::Conflict("No crawl is running".to_string()));
+  }
+
+  Ok(Json(MessageResponse {
+    message: "Indexing cancelled".to_string(),
+  }))
+}
+
+pub async fn delete_documents(
+  State(state): State<ApiState>,
+  Query(params): Query<DeleteDocumentsQuery>,
+) -> Result<Json<DeleteDocumentsResponse>, ApiError> {
+  let selector = match (params.url, params.domain) {
+    (Some(url), None) if !url.trim().is_empty() => DocumentSelector::Url(url.trim().to_string()),
+    (None, Some(domain)) if !domain.trim().is_empty() => DocumentSelector::Domain(domain.trim().to_string()),
+    _ => return Err(ApiError::InvalidParameter("Expected either `url` or `domain`".to_string())),
+  };
+
+  // Crawls started outside the API ( the CLI ) are rejected by `crawler::delete_documents`
+  if state.is_crawling() {
+    return Err(ApiError::Conflict("The index is being written by a crawl, try again later".to_string()));
+  }
+
+  let deleted = tokio::task::spawn_blocking(move || crawler::delete_documents(&state.index_path, &selector))
+    .await.map_err(|e| ApiError::Internal(e.into()))??;
+  tracing::info!("Deleted {} pages", deleted);
+
+  Ok(Json(DeleteDocumentsResponse { deleted }))
+}
+
+// Reloads the API keys, and reopens the searcher so it picks up the current synonyms and click-through priors
+pub async fn reload_config(State(state): State<ApiState>) -> Result<Json<ReloadConfigResponse>, ApiError> {
+  let api_keys = ApiKeys::from_config(&state.data_dir)
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid API keys: {}", e)))?;
+  let num_keys = api_keys.len();
+  state.reload(api_keys);
+
+  Ok(Json(ReloadConfigResponse {
+    message: "Config reloaded".to_string(),
+    api_keys: num_keys,
+  }))
+}
\ No newline at end of file
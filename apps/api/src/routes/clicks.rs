@@ -0,0 +1,55 @@
+use api_types::v1::{ClickRequest, GoQuery};
+use axum::{extract::{Query, State}, http::StatusCode, response::Redirect, Json};
+
+use crate::{create_routes::ApiState, query_log::ClickLogEntry};
+use super::ApiError;
+
+// Only the results logged for the search `query_id` are recorded and redirected to, so `/api/go`
+// isn't an open redirect. Any url of a crawled site isn't enough: sites include hosting domains
+// ( `readthedocs.io` ) whose subdomains anyone can claim
+fn validate_result_url(state: &ApiState, query_id: &str, url: &str) -> Result<(), ApiError> {
+  let is_result_url = !query_id.is_empty() && state.query_log
+    .with_entries(|searches| {
+      searches.iter().rev()
+        .find(|search| search.id == query_id)
+        .is_some_and(|search| search.top_urls.iter().any(|result| result == url))
+    })
+    .map_err(|e| ApiError::Internal(e.into()))?;
+
+  if !is_result_url {
+    return Err(ApiError::InvalidParameter(format!("Not a result of search `{}`: {}", query_id, url)));
+  }
+  Ok(())
+}
+
+fn record_click(state: &ApiState, query_id: String, url: String, position: usize) -> Result<(), ApiError> {
+  if position == 0 {
+    return Err(ApiError::InvalidParameter("`position` starts at 1".to_string()));
+  }
+  validate_result_url(state, &query_id, &url)?;
+
+  let entry = ClickLogEntry {
+    timestamp: chrono::Utc::now().timestamp_millis(),
+    query_id,
+    url,
+    position,
+  };
+  state.click_log.append(&entry).map_err(|e| ApiError::Internal(e.into()))
+}
+
+pub async fn click(
+  State(state): State<ApiState>,
+  Json(click): Json<ClickRequest>,
+) -> Result<StatusCode, ApiError> {
+  record_click(&state, click.query_id, click.url, click.position)?;
+  Ok(StatusCode::NO_CONTENT)
+}
+
+// Redirect style click tracking for links: `/api/go?u=<url>&qid=<query id>&pos=<position>`
+pub async fn go(
+  State(state): State<ApiState>,
+  Query(params): Query<GoQuery>,
+) -> Result<Redirect, ApiError> {
+  record_click(&state, params.qid, params.u.clone(), params.pos)?;
+  Ok(Redirect::to(&params.u))
+}
@@ -1,37 +1,162 @@
+use std::io;
+
+use api_types::v1::ErrorResponse;
 use axum::{response::{IntoResponse, Response}, Json};
-use hyper::StatusCode;
+use axum::http::{header, HeaderValue, StatusCode, Uri};
+use chrono::{DateTime, NaiveDate};
+use crawler::CrawlError;
+use searcher::SearchError;
+use tantivy::TantivyError;
 
 pub mod health_check;
 pub mod trigger_indexing;
 pub mod search;
 pub mod pages_per_site;
 pub mod scraped_urls;
+pub mod query_analytics;
+pub mod admin;
+pub mod clicks;
+pub mod stats;
+pub mod openapi;
 
 // Error handling
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
-    #[error("Search error: {0}")]
-    SearchError(String),
-    #[error("Invalid query: {0}")]
-    QueryError(String),
+    #[error("{0}")]
+    InvalidParameter(String),
+    #[error("{0}")]
+    InvalidQuery(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("Too many requests, retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+    #[error("{0}")]
+    IndexUnavailable(String),
     #[error("Internal server error")]
     Internal(#[from] anyhow::Error),
 }
 
+impl ApiError {
+  // Stable machine-readable code of the error, clients should match on it rather than on the message
+  pub fn code(&self) -> &'static str {
+      match self {
+          ApiError::InvalidParameter(_) => "invalid_parameter",
+          ApiError::InvalidQuery(_) => "invalid_query",
+          ApiError::Unauthorized(_) => "unauthorized",
+          ApiError::Forbidden(_) => "forbidden",
+          ApiError::NotFound(_) => "not_found",
+          ApiError::Conflict(_) => "conflict",
+          ApiError::RateLimited { .. } => "rate_limited",
+          ApiError::IndexUnavailable(_) => "index_unavailable",
+          ApiError::Internal(_) => "internal_error",
+      }
+  }
+
+  pub fn status(&self) -> StatusCode {
+      match self {
+          ApiError::InvalidParameter(_) | ApiError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+          ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+          ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+          ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+          ApiError::Conflict(_) => StatusCode::CONFLICT,
+          ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+          ApiError::IndexUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+          ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+      }
+  }
+}
+
+impl From<searcher::SearchError> for ApiError {
+  fn from(err: searcher::SearchError) -> Self {
+      match err {
+          SearchError::InvalidQuery(_) | SearchError::UnknownField(_) => ApiError::InvalidQuery(err.to_string()),
+          SearchError::IndexNotFound(_) | SearchError::MissingField(_) => ApiError::IndexUnavailable(err.to_string()),
+          SearchError::Index(_) => ApiError::Internal(err.into()),
+      }
+  }
+}
+
+impl From<crawler::CrawlError> for ApiError {
+  fn from(err: crawler::CrawlError) -> Self {
+      match err {
+          CrawlError::Index(TantivyError::LockFailure(..)) => ApiError::Conflict("The index is being written by a crawl, try again later".to_string()),
+          // Another process holds the snapshots' lock, see `schema::SnapshotStore::lock_current`
+          CrawlError::Snapshot(ref e) if e.kind() == io::ErrorKind::WouldBlock => ApiError::Conflict("The index is being written by a crawl or import, try again later".to_string()),
+          CrawlError::Index(TantivyError::OpenDirectoryError(_) | TantivyError::OpenReadError(_)) => ApiError::IndexUnavailable(err.to_string()),
+          CrawlError::Query(_) => ApiError::InvalidParameter(err.to_string()),
+          CrawlError::MissingField(_) => ApiError::IndexUnavailable(err.to_string()),
+          CrawlError::InvalidDocument { .. } => ApiError::InvalidParameter(err.to_string()),
+          CrawlError::Index(_) | CrawlError::LinkGraph(_) | CrawlError::Documents(_) | CrawlError::Snapshot(_) => ApiError::Internal(err.into()),
+      }
+  }
+}
+
+// Code and message of an error response. Attached to the response so the `error_body` middleware
+// can add the request id to the body
+#[derive(Debug, Clone)]
+pub struct ErrorInfo {
+  pub code: &'static str,
+  pub message: String,
+  // Cause of an internal error, logged but not sent to the client
+  pub cause: Option<String>,
+}
+
+impl ErrorInfo {
+  pub fn body(&self, request_id: Option<&str>) -> ErrorResponse {
+      ErrorResponse {
+          error: self.message.clone(),
+          code: self.code.to_string(),
+          request_id: request_id.map(str::to_string),
+      }
+  }
+}
+
 impl IntoResponse for ApiError {
   fn into_response(self) -> Response {
-      let (status, message) = match self {
-          ApiError::SearchError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-          ApiError::QueryError(msg) => (StatusCode::BAD_REQUEST, msg),
-          ApiError::Internal(_) => (
-              StatusCode::INTERNAL_SERVER_ERROR,
-              "Internal server error".to_string(),
-          ),
+      let cause = match &self {
+          ApiError::Internal(err) => Some(format!("{:#}", err)),
+          _ => None,
+      };
+      let info = ErrorInfo {
+          code: self.code(),
+          message: self.to_string(),
+          cause,
       };
 
-      Json(serde_json::json!({
-          "error": message
-      }))
-      .into_response()
+      let mut response = (self.status(), Json(info.body(None))).into_response();
+      match self {
+          ApiError::Unauthorized(_) => {
+              response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+          },
+          ApiError::RateLimited { retry_after_secs } => {
+              response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+          },
+          _ => {},
+      }
+      response.extensions_mut().insert(info);
+      response
   }
-}
\ No newline at end of file
+}
+
+// Fallback of the router, so unknown routes get the standard error body
+pub async fn not_found(uri: Uri) -> ApiError {
+  ApiError::NotFound(format!("No route for {}", uri.path()))
+}
+
+// Parses a date parameter into epoch milliseconds
+pub fn parse_date_param(name: &str, value: &str) -> Result<i64, ApiError> {
+  DateTime::parse_from_rfc3339(value)
+    .map(|date| date.timestamp_millis())
+    .ok()
+    .or_else(|| {
+      let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+      Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis())
+    })
+    .ok_or_else(|| ApiError::InvalidParameter(format!("Invalid `{}` date: {}", name, value)))
+}
@@ -0,0 +1,12 @@
+use std::sync::OnceLock;
+
+use axum::Json;
+use serde_json::Value;
+
+use crate::openapi::openapi_document;
+
+// The document only changes with the code, it is built once
+pub async fn openapi() -> Json<Value> {
+  static DOCUMENT: OnceLock<Value> = OnceLock::new();
+  Json(DOCUMENT.get_or_init(openapi_document).clone())
+}
\ No newline at end of file
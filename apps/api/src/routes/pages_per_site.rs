@@ -1,24 +1,19 @@
-use axum::Json;
-use serde::Serialize;
+use api_types::v1::PagesPerSiteEntry;
+use axum::{extract::State, Json};
 
+use crate::create_routes::ApiState;
 use super::ApiError;
 
-#[derive(Debug, Serialize)]
-pub struct PagesPerSiteEntry {
-  pub domain: String,
-  pub count: usize
-}
-
-pub async fn pages_per_site() -> Result<Json<Vec<PagesPerSiteEntry>>, ApiError> {
-  let searcher = searcher::DocSearcher::new("./index".to_string()).unwrap();
-  let results = searcher.get_pages_per_site().unwrap();
+pub async fn pages_per_site(State(state): State<ApiState>) -> Result<Json<Vec<PagesPerSiteEntry>>, ApiError> {
+  let searcher = state.searcher()?;
+  let results = searcher.get_pages_per_site()?;
 
   let response = results.iter().map(|e| {
-    return PagesPerSiteEntry {
+    PagesPerSiteEntry {
       domain: e.0.to_string(),
       count: e.1
-    };
+    }
   }).collect();
 
-  return Ok(Json(response));
+  Ok(Json(response))
 }
\ No newline at end of file
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashMap};
+
+use api_types::v1::{AnalyticsQuery, LatencyPercentiles, QueryCount, VolumeBucket};
+use axum::{extract::{Query, State}, Json};
+
+use crate::{create_routes::ApiState, query_log::QueryLogEntry};
+use super::{parse_date_param, ApiError};
+
+const DEFAULT_LIMIT: usize = 20;
+
+// Calls `f` with the searches logged since the `since` parameter
+fn with_logged_searches<R>(
+  state: &ApiState,
+  params: &AnalyticsQuery,
+  f: impl FnOnce(&mut dyn Iterator<Item = &QueryLogEntry>) -> R,
+) -> Result<R, ApiError> {
+  let since = params.since.as_deref().map(|since| parse_date_param("since", since)).transpose()?;
+
+  state.query_log
+    .with_entries(|entries| f(&mut entries.iter().filter(|entry| since.is_none_or(|since| entry.timestamp >= since))))
+    .map_err(|e| ApiError::Internal(e.into()))
+}
+
+// Queries by number of searches, most searched first
+fn count_queries<'a>(entries: impl Iterator<Item = &'a QueryLogEntry>, limit: usize) -> Vec<QueryCount> {
+  let mut counts: HashMap<String, QueryCount> = HashMap::new();
+  for entry in entries {
+    let query = searcher::normalize_query(&entry.query);
+    let count = counts.entry(query.clone()).or_insert(QueryCount { query, count: 0, last_seen: 0 });
+    count.count += 1;
+    count.last_seen = count.last_seen.max(entry.timestamp);
+  }
+
+  let mut counts: Vec<QueryCount> = counts.into_values().collect();
+  counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| b.last_seen.cmp(&a.last_seen)));
+  counts.truncate(limit);
+  counts
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+  if sorted.is_empty() {
+    return 0.0;
+  }
+  let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+  sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn latency_percentiles<'a>(entries: impl Iterator<Item = &'a QueryLogEntry>) -> LatencyPercentiles {
+  let mut latencies: Vec<f64> = entries.map(|entry| entry.took_ms).collect();
+  latencies.sort_by(|a, b| a.total_cmp(b));
+
+  LatencyPercentiles {
+    count: latencies.len(),
+    p50: percentile(&latencies, 50.0),
+    p90: percentile(&latencies, 90.0),
+    p95: percentile(&latencies, 95.0),
+    p99: percentile(&latencies, 99.0),
+    max: latencies.last().copied().unwrap_or_default(),
+  }
+}
+
+// Number of searches per `bucket_ms` window, oldest first. Windows without searches are left out
+fn volume_buckets<'a>(entries: impl Iterator<Item = &'a QueryLogEntry>, bucket_ms: i64) -> Vec<VolumeBucket> {
+  let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+  for entry in entries {
+    *counts.entry(entry.timestamp - entry.timestamp.rem_euclid(bucket_ms)).or_default() += 1;
+  }
+
+  counts.into_iter().map(|(start, count)| VolumeBucket { start, count }).collect()
+}
+
+pub async fn top_queries(
+  State(state): State<ApiState>,
+  Query(params): Query<AnalyticsQuery>,
+) -> Result<Json<Vec<QueryCount>>, ApiError> {
+  let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+  with_logged_searches(&state, &params, |entries| Json(count_queries(entries, limit)))
+}
+
+pub async fn zero_result_queries(
+  State(state): State<ApiState>,
+  Query(params): Query<AnalyticsQuery>,
+) -> Result<Json<Vec<QueryCount>>, ApiError> {
+  let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+  with_logged_searches(&state, &params, |entries| Json(count_queries(entries.filter(|entry| entry.total == 0), limit)))
+}
+
+pub async fn latency(
+  State(state): State<ApiState>,
+  Query(params): Query<AnalyticsQuery>,
+) -> Result<Json<LatencyPercentiles>, ApiError> {
+  with_logged_searches(&state, &params, |entries| Json(latency_percentiles(entries)))
+}
+
+pub async fn query_volume(
+  State(state): State<ApiState>,
+  Query(params): Query<AnalyticsQuery>,
+) -> Result<Json<Vec<VolumeBucket>>, ApiError> {
+  let bucket_ms: i64 = match params.interval.as_deref() {
+    Some("hour") => 60 * 60 * 1000,
+    None | Some("day") => 24 * 60 * 60 * 1000,
+    Some(interval) => return Err(ApiError::InvalidParameter(format!("Invalid interval: {}", interval))),
+  };
+
+  with_logged_searches(&state, &params, |entries| Json(volume_buckets(entries, bucket_ms)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::query_log::QueryFilters;
+
+  fn entry(query: &str, timestamp: i64, total: usize, took_ms: f64) -> QueryLogEntry {
+    QueryLogEntry {
+      id: String::new(),
+      timestamp,
+      query: query.to_string(),
+      filters: QueryFilters::default(),
+      total,
+      took_ms,
+      top_urls: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn queries_are_counted_by_normalized_query() {
+    let entries = [
+      entry("Rust vec", 1000, 5, 1.0),
+      entry("go maps", 500, 0, 1.0),
+      entry("  rust   VEC ", 3000, 5, 1.0),
+      entry("go maps", 2000, 0, 1.0),
+      entry("python", 4000, 7, 1.0),
+    ];
+
+    let counts: Vec<(String, usize, i64)> = count_queries(entries.iter(), 10).into_iter()
+      .map(|count| (count.query, count.count, count.last_seen))
+      .collect();
+    // Ties are broken by the latest search
+    assert_eq!(counts, [("rust vec".to_string(), 2, 3000), ("go maps".to_string(), 2, 2000), ("python".to_string(), 1, 4000)]);
+
+    assert_eq!(count_queries(entries.iter(), 1).len(), 1);
+    assert!(count_queries([].iter(), 10).is_empty());
+  }
+
+  #[test]
+  fn latency_percentiles_are_nearest_rank() {
+    // 1 to 10 ms, out of order
+    let entries: Vec<QueryLogEntry> = [7, 3, 10, 1, 5, 9, 2, 8, 4, 6].into_iter()
+      .map(|took_ms| entry("q", 0, 1, took_ms as f64))
+      .collect();
+
+    let latency = latency_percentiles(entries.iter());
+    assert_eq!(latency.count, 10);
+    assert_eq!((latency.p50, latency.p90, latency.p95, latency.p99, latency.max), (5.0, 9.0, 10.0, 10.0, 10.0));
+
+    let single = latency_percentiles([entry("q", 0, 1, 2.5)].iter());
+    assert_eq!((single.p50, single.p99, single.max), (2.5, 2.5, 2.5));
+
+    let empty = latency_percentiles([].iter());
+    assert_eq!((empty.count, empty.p50, empty.max), (0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn volume_is_counted_per_bucket_in_order() {
+    const HOUR: i64 = 60 * 60 * 1000;
+    let entries: Vec<QueryLogEntry> = [7_300_000, 0, HOUR - 1, -1, HOUR].into_iter()
+      .map(|timestamp| entry("q", timestamp, 1, 1.0))
+      .collect();
+
+    let buckets: Vec<(i64, usize)> = volume_buckets(entries.iter(), HOUR).into_iter()
+      .map(|bucket| (bucket.start, bucket.count))
+      .collect();
+    assert_eq!(buckets, [(-HOUR, 1), (0, 2), (HOUR, 1), (2 * HOUR, 1)]);
+  }
+}
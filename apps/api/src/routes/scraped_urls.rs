@@ -1,32 +1,27 @@
-use axum::{extract::Query, Json};
-use serde::{Deserialize, Serialize};
+use api_types::v1::{ScrapedUrlsQuery, ScrapedUrlsResponse};
+use axum::{extract::{Query, State}, Json};
 
+use crate::create_routes::ApiState;
 use super::ApiError;
 
-#[derive(Debug, Deserialize)]
-pub struct ScrapedUrlsQuery{
-    domain: Option<String>,
-    limit: Option<usize>,
-    offset: Option<usize>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ScrapedUrlsResponse {
-    urls: Vec<String>,
-    total: usize,
-}
-
 pub async fn scraped_urls(
+  State(state): State<ApiState>,
   Query(params): Query<ScrapedUrlsQuery>
 ) -> Result<Json<ScrapedUrlsResponse>, ApiError> {
-  let searcher = searcher::DocSearcher::new("./index".to_string()).unwrap();
+  let limit = params.limit.unwrap_or(10);
+  if !(1..=config::MAX_CRAWLED_URLS_LIMIT).contains(&limit) {
+    return Err(ApiError::InvalidParameter(format!("`limit` must be between 1 and {}", config::MAX_CRAWLED_URLS_LIMIT)));
+  }
+  let offset = params.offset.unwrap_or(0);
+  if offset > config::MAX_CRAWLED_URLS_OFFSET {
+    return Err(ApiError::InvalidParameter(format!("`offset` must be at most {}", config::MAX_CRAWLED_URLS_OFFSET)));
+  }
 
-  let limit = params.limit.unwrap_or_else(|| {10});
-  let offset = params.offset.unwrap_or_else(|| {0});
-  let results = searcher.get_crawled_urls(params.domain, limit, offset).unwrap();
+  let searcher = state.searcher()?;
+  let results = searcher.get_crawled_urls(params.domain, limit, offset)?;
 
-  return Ok(Json(ScrapedUrlsResponse {
+  Ok(Json(ScrapedUrlsResponse {
     urls: results.urls,
     total: results.total,
-  }));
+  }))
 }
\ No newline at end of file
@@ -1,46 +1,77 @@
-use axum::{extract::Query, Json};
-use serde::{Deserialize, Serialize};
+use api_types::v1::{QueryExpansion, ScoreExplanation, SearchQuery, SearchResponse, SearchResult};
+use axum::{extract::{Query, State}, Json};
 
-use super::ApiError;
+use crate::{create_routes::ApiState, query_log::{self, QueryLogEntry}};
+use super::{parse_date_param, ApiError};
 
-#[derive(Debug, Deserialize)]
-pub struct SearchQuery {
-    q: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct SearchResult {
-    title: String,
-    heading: String,
-    url: String,
-    snippet: String,
-    score: f32,
-    scraped_at: i64,
-}
-
-#[derive(Debug, Serialize)]
-pub struct SearchResponse {
-    results: Vec<SearchResult>,
-    total: usize,
-    query: String,
-    took_ms: f64,
+// Tantivy's explanation only implements `Serialize`, it is converted through its JSON
+fn score_explanation(explanation: &searcher::ScoreExplanation) -> Option<ScoreExplanation> {
+  serde_json::to_value(explanation).and_then(serde_json::from_value).ok()
 }
 
 pub async fn search(
+  State(state): State<ApiState>,
   Query(params): Query<SearchQuery>,
 ) -> Result<Json<SearchResponse>, ApiError> {
   let start = std::time::Instant::now();
 
   if params.q.trim().is_empty() {
-    return Err(ApiError::QueryError("Query cannot be empty".to_string()));
+    return Err(ApiError::InvalidQuery("Query cannot be empty".to_string()));
   }
 
-  let searcher = searcher::DocSearcher::new("./index".to_string()).unwrap();
-  let results = searcher.search(params.q.as_str(), 10).unwrap();
+  let sort = match params.sort.as_deref() {
+    None | Some("relevance") => searcher::SortOrder::Relevance,
+    Some("date") => searcher::SortOrder::Date,
+    Some(sort) => return Err(ApiError::InvalidParameter(format!("Invalid sort: {}", sort))),
+  };
+  let after = params.after.as_deref().map(|after| parse_date_param("after", after)).transpose()?;
+  let before = params.before.as_deref().map(|before| parse_date_param("before", before)).transpose()?;
+
+  let mut defaults = searcher::SearchOptions::default();
+  let overrides = [("fields", &params.fields), ("boost", &params.boost), ("operator", &params.operator), ("site_boost", &params.site_boost)];
+  for (name, value) in overrides {
+    if let Some(value) = value {
+      defaults.apply_override(name, value).map_err(ApiError::InvalidParameter)?;
+    }
+  }
+  let options = searcher::SearchOptions {
+    phrase_boost: params.phrase_boost.unwrap_or(defaults.phrase_boost),
+    phrase_slop: params.slop.unwrap_or(defaults.phrase_slop),
+    explain: params.explain.unwrap_or(defaults.explain),
+    click_weight: params.clicks.unwrap_or(defaults.click_weight),
+    lang: params.lang.map(|lang| lang.to_lowercase()),
+    preferred_lang: params.prefer.map(|lang| lang.to_lowercase()).or(defaults.preferred_lang),
+    version: params.version,
+    expand_synonyms: params.synonyms.unwrap_or(defaults.expand_synonyms),
+    sort,
+    after,
+    before,
+    freshness_weight: params.freshness.unwrap_or(defaults.freshness_weight),
+    freshness_half_life_days: params.half_life_days.unwrap_or(defaults.freshness_half_life_days),
+    authority_weight: params.authority.unwrap_or(defaults.authority_weight),
+    max_per_site: match params.max_per_site {
+      Some(0) => None,
+      Some(max) => Some(max),
+      None => defaults.max_per_site,
+    },
+    ..defaults
+  };
+  options.validate().map_err(ApiError::InvalidParameter)?;
+
+  let searcher = state.searcher()?;
+  let search_results = searcher.search(params.q.as_str(), &options)?;
 
   let response = SearchResponse {
-    total: results.len(),
-    results: results
+    query_id: uuid::Uuid::new_v4().to_string(),
+    total: search_results.results.len(),
+    expansions: search_results.expansions
+        .into_iter()
+        .map(|e| QueryExpansion {
+            term: e.term,
+            alternatives: e.alternatives,
+        })
+        .collect(),
+    results: search_results.results
         .into_iter()
         .map(|r| SearchResult {
             title: r.title,
@@ -48,12 +79,40 @@ pub async fn search(
             url: r.url,
             snippet: r.content_snippet,
             score: r.score,
-            scraped_at: r.scraped_at
+            scraped_at: r.scraped_at,
+            last_modified: r.last_modified,
+            product: r.product,
+            version: r.version,
+            other_versions: r.other_versions,
+            also_at: r.also_at,
+            more_from_site: r.more_from_site,
+            explanation: r.explanation.as_ref().and_then(score_explanation),
         })
         .collect(),
+    parsed_query: search_results.parsed_query,
     query: params.q,
     took_ms: start.elapsed().as_secs_f64() * 1000.0,
   };
 
-  return Ok(Json(response));
+  let entry = QueryLogEntry {
+    id: response.query_id.clone(),
+    timestamp: chrono::Utc::now().timestamp_millis(),
+    query: response.query.clone(),
+    filters: query_log::QueryFilters {
+      lang: options.lang,
+      version: options.version,
+      sort: params.sort,
+      after: params.after,
+      before: params.before,
+    },
+    total: response.total,
+    took_ms: response.took_ms,
+    top_urls: response.results.iter().take(query_log::LOGGED_RESULTS).map(|r| r.url.clone()).collect(),
+  };
+  // A search shouldn't fail because it couldn't be logged
+  if let Err(e) = state.query_log.append(&entry) {
+    tracing::warn!("Failed to log search: {}", e);
+  }
+
+  Ok(Json(response))
 }
\ No newline at end of file
@@ -0,0 +1,23 @@
+use api_types::v1::{CacheStats, StatsResponse};
+use axum::{extract::State, Json};
+
+use crate::create_routes::ApiState;
+use super::ApiError;
+
+pub async fn stats(State(state): State<ApiState>) -> Result<Json<StatsResponse>, ApiError> {
+  let searcher = state.searcher()?;
+  let cache = searcher.cache_stats();
+  let lookups = cache.hits + cache.misses;
+
+  Ok(Json(StatsResponse {
+    num_docs: searcher.num_docs(),
+    cache: CacheStats {
+      hits: cache.hits,
+      misses: cache.misses,
+      hit_rate: if lookups == 0 { 0.0 } else { cache.hits as f64 / lookups as f64 },
+      entries: cache.entries,
+      bytes: cache.bytes,
+      capacity_bytes: cache.capacity_bytes,
+    },
+  }))
+}
\ No newline at end of file
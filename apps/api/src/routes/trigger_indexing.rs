@@ -1,13 +1,20 @@
-use axum::{response::IntoResponse, Json};
-use hyper::StatusCode;
-use tower_http::trace::ResponseBody;
+use api_types::v1::MessageResponse;
+use axum::{extract::State, Json};
 
-pub async fn trigger_indexing() -> impl IntoResponse {
-  tokio::task::spawn_blocking(|| {
-    tokio::runtime::Handle::current().block_on(crawler::start_indexing())
+use crate::create_routes::ApiState;
+use super::ApiError;
+
+pub async fn trigger_indexing(State(state): State<ApiState>) -> Result<Json<MessageResponse>, ApiError> {
+  let cancel = state.start_crawl()?;
+  tokio::task::spawn_blocking(move || {
+    if let Err(e) = tokio::runtime::Handle::current().block_on(crawler::start_indexing(&state.index_path, &Default::default(), cancel)) {
+      tracing::error!("Indexing failed: {}", e);
+    }
+    state.refresh_snapshot();
+    state.finish_crawl();
   });
 
-  return Json(serde_json::json!({
-    "message": "Indexing triggered"
-  }));
+  Ok(Json(MessageResponse {
+    message: "Indexing triggered".to_string(),
+  }))
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+// Pins the JSON shapes of the `/api/v1` types. These tests failing means a breaking change for
+// clients, which belongs in a new API version instead
+use api_types::{v1::*, ApiObject};
+use serde_json::json;
+
+fn field_names<T: ApiObject>() -> Vec<&'static str> {
+  T::fields().into_iter().map(|field| field.name).collect()
+}
+
+#[test]
+fn search_response() {
+  let response = SearchResponse {
+    query_id: "5f0c".to_string(),
+    results: vec![SearchResult {
+      title: "Iterator".to_string(),
+      heading: "Examples".to_string(),
+      url: "https://doc.rust-lang.org/std/iter/trait.Iterator.html".to_string(),
+      snippet: "An interface for dealing with iterators".to_string(),
+      score: 1.5,
+      scraped_at: 1700000000000,
+      last_modified: 1690000000000,
+      product: Some("rust".to_string()),
+      version: None,
+      other_versions: vec!["1.70".to_string()],
+      also_at: vec![],
+      more_from_site: 2,
+      explanation: None,
+    }],
+    expansions: vec![QueryExpansion { term: "iter".to_string(), alternatives: vec!["iterator".to_string()] }],
+    total: 1,
+    query: "iter".to_string(),
+    took_ms: 3.5,
+    parsed_query: None,
+  };
+
+  assert_eq!(serde_json::to_value(&response).unwrap(), json!({
+    "query_id": "5f0c",
+    "results": [{
+      "title": "Iterator",
+      "heading": "Examples",
+      "url": "https://doc.rust-lang.org/std/iter/trait.Iterator.html",
+      "snippet": "An interface for dealing with iterators",
+      "score": 1.5,
+      "scraped_at": 1700000000000i64,
+      "last_modified": 1690000000000i64,
+      "product": "rust",
+      "version": null,
+      "other_versions": ["1.70"],
+      "also_at": [],
+      "more_from_site": 2,
+    }],
+    "expansions": [{ "term": "iter", "alternatives": ["iterator"] }],
+    "total": 1,
+    "query": "iter",
+    "took_ms": 3.5,
+  }));
+}
+
+#[test]
+fn explained_search_result() {
+  let explanation = ScoreExplanation {
+    value: 2.0,
+    description: "Final score".to_string(),
+    details: Some(vec![ScoreExplanation { value: 2.0, description: "idf".to_string(), details: None, context: vec![] }]),
+    context: vec!["title".to_string()],
+  };
+
+  assert_eq!(serde_json::to_value(&explanation).unwrap(), json!({
+    "value": 2.0,
+    "description": "Final score",
+    "details": [{ "value": 2.0, "description": "idf", "context": [] }],
+    "context": ["title"],
+  }));
+}
+
+#[test]
+fn search_query_parameters() {
+  assert_eq!(field_names::<SearchQuery>(), [
+    "q", "lang", "prefer", "version", "synonyms", "sort", "after", "before", "freshness", "half_life_days", "authority",
+    "max_per_site", "site_boost", "fields", "boost", "operator", "phrase_boost", "slop", "explain", "clicks",
+  ]);
+  assert_eq!(field_names::<GoQuery>(), ["u", "qid", "pos"]);
+  assert_eq!(field_names::<ScrapedUrlsQuery>(), ["domain", "limit", "offset"]);
+  assert_eq!(field_names::<AnalyticsQuery>(), ["since", "limit", "interval"]);
+  assert_eq!(field_names::<DeleteDocumentsQuery>(), ["url", "domain"]);
+}
+
+#[test]
+fn click_request() {
+  let click: ClickRequest = serde_json::from_value(json!({
+    "query_id": "5f0c",
+    "url": "https://doc.rust-lang.org/std/iter/trait.Iterator.html",
+    "position": 1,
+  })).unwrap();
+
+  assert_eq!(click.query_id, "5f0c");
+  assert_eq!(click.position, 1);
+}
+
+#[test]
+fn analytics_responses() {
+  let pages = PagesPerSiteEntry { domain: "doc.rust-lang.org".to_string(), count: 10 };
+  assert_eq!(serde_json::to_value(&pages).unwrap(), json!({ "domain": "doc.rust-lang.org", "count": 10 }));
+
+  let urls = ScrapedUrlsResponse { urls: vec!["https://docs.python.org/3/".to_string()], total: 1 };
+  assert_eq!(serde_json::to_value(&urls).unwrap(), json!({ "urls": ["https://docs.python.org/3/"], "total": 1 }));
+
+  let count = QueryCount { query: "iterator".to_string(), count: 3, last_seen: 1700000000000 };
+  assert_eq!(serde_json::to_value(&count).unwrap(), json!({ "query": "iterator", "count": 3, "last_seen": 1700000000000i64 }));
+
+  let latency = LatencyPercentiles { count: 4, p50: 1.0, p90: 2.0, p95: 3.0, p99: 4.0, max: 5.0 };
+  assert_eq!(serde_json::to_value(&latency).unwrap(), json!({ "count": 4, "p50": 1.0, "p90": 2.0, "p95": 3.0, "p99": 4.0, "max": 5.0 }));
+
+  let bucket = VolumeBucket { start: 1699920000000, count: 7 };
+  assert_eq!(serde_json::to_value(&bucket).unwrap(), json!({ "start": 1699920000000i64, "count": 7 }));
+}
+
+#[test]
+fn stats_response() {
+  let stats = StatsResponse {
+    num_docs: 120,
+    cache: CacheStats { hits: 3, misses: 1, hit_rate: 0.75, entries: 1, bytes: 2048, capacity_bytes: 67108864 },
+  };
+
+  assert_eq!(serde_json::to_value(&stats).unwrap(), json!({
+    "num_docs": 120,
+    "cache": { "hits": 3, "misses": 1, "hit_rate": 0.75, "entries": 1, "bytes": 2048, "capacity_bytes": 67108864 },
+  }));
+}
+
+#[test]
+fn admin_responses() {
+  let message = MessageResponse { message: "Indexing triggered".to_string() };
+  assert_eq!(serde_json::to_value(&message).unwrap(), json!({ "message": "Indexing triggered" }));
+
+  let deleted = DeleteDocumentsResponse { deleted: 2 };
+  assert_eq!(serde_json::to_value(&deleted).unwrap(), json!({ "deleted": 2 }));
+
+  let reloaded = ReloadConfigResponse { message: "Config reloaded".to_string(), api_keys: 1 };
+  assert_eq!(serde_json::to_value(&reloaded).unwrap(), json!({ "message": "Config reloaded", "api_keys": 1 }));
+}
+
+#[test]
+fn error_response() {
+  let error = ErrorResponse {
+    error: "Query cannot be empty".to_string(),
+    code: "invalid_query".to_string(),
+    request_id: Some("0d6e".to_string()),
+  };
+
+  assert_eq!(serde_json::to_value(&error).unwrap(), json!({
+    "error": "Query cannot be empty",
+    "code": "invalid_query",
+    "request_id": "0d6e",
+  }));
+}
@@ -0,0 +1,203 @@
+// Requests through the whole router against a small index, checking the `/api/v1` routes are served
+// and the `/api` aliases answer the same with the deprecation headers
+use std::{net::Ipv4Addr, path::Path, sync::{atomic::{AtomicU32, Ordering}, OnceLock}};
+
+use api::{create_router, ApiState};
+use axum::{body::{to_bytes, Body}, http::{Request, StatusCode}, response::Response, Router};
+use serde_json::Value;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+const PAGES: &str = r#"{"url": ["https://doc.rust-lang.org/std/vec/struct.Vec.html"], "title": ["Vec in std::vec"], "content": ["A contiguous growable array type, written as Vec<T>."]}
+{"url": ["https://go.dev/doc/effective_go"], "title": ["Effective Go"], "content": ["Do not communicate by sharing memory; instead, share memory by communicating."]}
+"#;
+
+// SHA-256 hashes of the `crawl-key` and `delete-key` API keys, which have the scope of their name
+const API_KEYS: &str = "crawler:ad5ed8ce0f7766801537870ca13abeb392addd9b70ff8d40c103451dcb286cbd:crawl
+deleter:5c6bf49eecb89033bb42f5eabf85175ed0dc1d0b128880cc4875c32a6ed2b5ea:delete
+";
+
+// Data directory with a published snapshot of `PAGES` in its index
+fn create_data_dir() -> TempDir {
+  let dir = tempfile::tempdir().unwrap();
+
+  let snapshots = schema::SnapshotStore::new(dir.path().join(config::INDEX_PATH));
+  let staging = snapshots.stage(false).unwrap();
+  let index = schema::open_or_create_index(staging.path()).unwrap();
+  crawler::import_documents(&index, PAGES.as_bytes()).unwrap();
+  snapshots.publish(staging, 1).unwrap();
+  dir
+}
+
+// Data directory of the API, shared by the tests which don't write to it. No API keys are configured
+fn data_dir() -> &'static Path {
+  static DATA_DIR: OnceLock<TempDir> = OnceLock::new();
+  DATA_DIR.get_or_init(create_data_dir).path()
+}
+
+fn router() -> Router {
+  create_router(ApiState::new(data_dir().to_path_buf()).unwrap())
+}
+
+async fn get(router: &Router, uri: &str) -> Response {
+  router.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap()
+}
+
+// Sends a request to an admin route with `headers`, from a new client address each time so the
+// admin rate limit doesn't answer first
+async fn admin(router: &Router, method: &str, uri: &str, headers: &[(&str, &str)]) -> Response {
+  static CLIENTS: AtomicU32 = AtomicU32::new(1);
+  let client = Ipv4Addr::from(CLIENTS.fetch_add(1, Ordering::Relaxed));
+  let mut request = Request::builder().method(method).uri(uri).header("x-forwarded-for", client.to_string());
+  for (name, value) in headers {
+    request = request.header(*name, *value);
+  }
+  router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+async fn json(response: Response) -> Value {
+  serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+}
+
+// Drops the fields which differ between any two requests
+fn without(mut body: Value, fields: &[&str]) -> Value {
+  body.as_object_mut().unwrap().retain(|field, _| !fields.contains(&field.as_str()));
+  body
+}
+
+#[tokio::test]
+async fn v1_routes_are_served() {
+  let router = router();
+
+  for uri in ["/api/v1/health", "/api/v1/search?q=vec", "/api/v1/crawled_urls", "/api/v1/analytics", "/api/v1/stats", "/api/v1/openapi.json"] {
+    let response = get(&router, uri).await;
+    assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+    assert!(response.headers().get("deprecation").is_none(), "{}", uri);
+  }
+
+  let search = json(get(&router, "/api/v1/search?q=vec").await).await;
+  assert_eq!(search["results"][0]["url"], "https://doc.rust-lang.org/std/vec/struct.Vec.html");
+  assert_eq!(json(get(&router, "/api/v1/stats").await).await["num_docs"], 2);
+}
+
+#[tokio::test]
+async fn aliases_answer_like_v1_and_link_their_successor() {
+  let router = router();
+
+  for route in ["/crawled_urls", "/analytics", "/openapi.json", "/health"] {
+    let v1 = get(&router, &format!("/api/v1{}", route)).await;
+    let alias = get(&router, &format!("/api{}", route)).await;
+
+    assert_eq!(alias.status(), v1.status(), "{}", route);
+    assert_eq!(alias.headers()["deprecation"], "true", "{}", route);
+    assert_eq!(alias.headers()["link"], format!("</api/v1{}>; rel=\"successor-version\"", route));
+    let v1_body = to_bytes(v1.into_body(), usize::MAX).await.unwrap();
+    let alias_body = to_bytes(alias.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(alias_body, v1_body, "{}", route);
+  }
+
+  // Searches get a fresh id and timing, the rest of the response is the same
+  let v1 = json(get(&router, "/api/v1/search?q=memory").await).await;
+  assert_eq!(v1["results"][0]["url"], "https://go.dev/doc/effective_go");
+  let alias = get(&router, "/api/search?q=memory").await;
+  assert_eq!(alias.headers()["link"], "</api/v1/search>; rel=\"successor-version\"");
+  assert_eq!(without(json(alias).await, &["query_id", "took_ms"]), without(v1, &["query_id", "took_ms"]));
+}
+
+#[tokio::test]
+async fn errors_are_the_same_on_both_paths() {
+  let router = router();
+
+  let v1 = get(&router, "/api/v1/search?q=vec&freshness=2").await;
+  let alias = get(&router, "/api/search?q=vec&freshness=2").await;
+  assert_eq!((v1.status(), alias.status()), (StatusCode::BAD_REQUEST, StatusCode::BAD_REQUEST));
+  assert_eq!(alias.headers()["deprecation"], "true");
+  assert_eq!(without(json(alias).await, &["request_id"]), without(json(v1).await, &["request_id"]));
+
+  let unknown = get(&router, "/api/v2/search?q=vec").await;
+  assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn crawled_urls_pages_are_bounded() {
+  let router = router();
+
+  let page = json(get(&router, "/api/v1/crawled_urls?limit=1&offset=1").await).await;
+  assert_eq!((page["urls"].as_array().unwrap().len(), &page["total"]), (1, &Value::from(2)));
+
+  for query in ["limit=0", "limit=101", "limit=1000000000000", "offset=10001", "limit=100&offset=1000000000000"] {
+    let response = get(&router, &format!("/api/v1/crawled_urls?{}", query)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+    assert_eq!(json(response).await["code"], "invalid_parameter", "{}", query);
+  }
+}
+
+#[tokio::test]
+async fn clicks_only_redirect_to_the_results_of_their_search() {
+  let router = router();
+  let search = json(get(&router, "/api/v1/search?q=vec").await).await;
+  let query_id = search["query_id"].as_str().unwrap();
+  let url = search["results"][0]["url"].as_str().unwrap();
+
+  let go = get(&router, &format!("/api/v1/go?u={}&qid={}&pos=1", url, query_id)).await;
+  assert_eq!(go.status(), StatusCode::SEE_OTHER);
+  assert_eq!(go.headers()["location"], url);
+
+  // A subdomain of a crawled hosting site, a crawled page which wasn't a result, and an unknown search
+  for (url, query_id) in [
+    ("https://attacker.readthedocs.io/en/latest/", query_id),
+    ("https://go.dev/doc/effective_go", query_id),
+    (url, "unknown"),
+    (url, ""),
+  ] {
+    let go = get(&router, &format!("/api/v1/go?u={}&qid={}&pos=1", url, query_id)).await;
+    assert_eq!(go.status(), StatusCode::BAD_REQUEST, "{} {}", url, query_id);
+    assert!(go.headers().get("location").is_none());
+  }
+}
+
+#[tokio::test]
+async fn admin_routes_are_rejected_without_configured_keys() {
+  let router = router();
+
+  for (method, uri) in [("POST", "/api/v1/index/trigger"), ("DELETE", "/api/v1/documents?url=https://go.dev/doc/effective_go")] {
+    for headers in [&[][..], &[("authorization", "Bearer crawl-key")], &[("x-api-key", "delete-key")]] {
+      let response = admin(&router, method, uri, headers).await;
+      assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{} {:?}", uri, headers);
+    }
+  }
+  assert_eq!(json(get(&router, "/api/v1/stats").await).await["num_docs"], 2);
+}
+
+#[tokio::test]
+async fn admin_routes_require_a_key_with_their_scope() {
+  let dir = create_data_dir();
+  std::fs::write(dir.path().join(config::API_KEYS_PATH), API_KEYS).unwrap();
+  let state = ApiState::new(dir.path().to_path_buf()).unwrap();
+  let router = create_router(state.clone());
+
+  for (method, uri, key, other_key) in [
+    ("DELETE", "/api/v1/documents?url=https://go.dev/doc/effective_go", "delete-key", "crawl-key"),
+    ("POST", "/api/v1/index/trigger", "crawl-key", "delete-key"),
+  ] {
+    let missing = admin(&router, method, uri, &[]).await;
+    assert_eq!(missing.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+    assert_eq!(missing.headers()["www-authenticate"], "Bearer");
+    assert_eq!(json(missing).await["code"], "unauthorized");
+
+    for headers in [[("authorization", "Bearer wrong-key")], [("x-api-key", "wrong-key")], [("authorization", key)]] {
+      let response = admin(&router, method, uri, &headers).await;
+      assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{} {:?}", uri, headers);
+    }
+
+    let forbidden = admin(&router, method, uri, &[("x-api-key", other_key)]).await;
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN, "{}", uri);
+    assert_eq!(json(forbidden).await["code"], "forbidden");
+
+    let bearer = format!("Bearer {}", key);
+    let allowed = admin(&router, method, uri, &[("authorization", &bearer)]).await;
+    assert_eq!(allowed.status(), StatusCode::OK, "{}", uri);
+  }
+  // Stops the crawl triggered above before the data directory is removed
+  state.stop_crawl().await;
+}
@@ -0,0 +1,71 @@
+use std::{path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use schema::SnapshotStore;
+
+// Arguments of a subcommand: positional arguments, `--name value` options and `--name` switches
+#[derive(Debug, Default)]
+pub struct Args {
+  pub positional: Vec<String>,
+  // In the order they were given
+  pub options: Vec<(String, String)>,
+  switches: Vec<String>,
+}
+
+impl Args {
+  // `switches` are the flags of the subcommand which don't take a value
+  pub fn parse(args: impl Iterator<Item = String>, switches: &[&str]) -> anyhow::Result<Self> {
+    let mut parsed = Args::default();
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+      let Some(name) = arg.strip_prefix("--") else {
+        parsed.positional.push(arg);
+        continue;
+      };
+
+      if switches.contains(&name) {
+        parsed.switches.push(name.to_string());
+      } else {
+        let value = args.next().ok_or_else(|| anyhow!("Missing value of `--{}`", name))?;
+        parsed.options.push((name.to_string(), value));
+      }
+    }
+    Ok(parsed)
+  }
+
+  pub fn value(&self, name: &str) -> Option<&str> {
+    self.options.iter().rev().find(|(option, _)| option == name).map(|(_, value)| value.as_str())
+  }
+
+  pub fn parsed<T: FromStr>(&self, name: &str) -> anyhow::Result<Option<T>> {
+    self.value(name)
+      .map(|value| value.parse().map_err(|_| anyhow!("Invalid `--{}`: {}", name, value)))
+      .transpose()
+  }
+
+  pub fn switch(&self, name: &str) -> bool {
+    self.switches.iter().any(|switch| switch == name)
+  }
+
+  // `--index`, shared by every subcommand. Holds the snapshots of the index
+  pub fn index_path(&self) -> PathBuf {
+    PathBuf::from(self.value("index").unwrap_or(config::INDEX_PATH))
+  }
+
+  pub fn snapshots(&self) -> SnapshotStore {
+    SnapshotStore::new(self.index_path())
+  }
+
+  // Directory of the snapshot being served, which the commands reading the index open
+  pub fn current_index(&self) -> anyhow::Result<PathBuf> {
+    Ok(self.snapshots().current_path()?)
+  }
+
+  // Rejects options the subcommand doesn't know, so typos aren't silently ignored
+  pub fn expect_options(&self, known: &[&str]) -> anyhow::Result<()> {
+    if let Some((name, _)) = self.options.iter().find(|(name, _)| name != "index" && !known.contains(&name.as_str())) {
+      bail!("Unknown option `--{}`", name);
+    }
+    Ok(())
+  }
+}
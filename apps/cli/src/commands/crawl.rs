@@ -0,0 +1,37 @@
+use anyhow::bail;
+use crawler::CrawlOptions;
+use tokio_util::sync::CancellationToken;
+
+use crate::args::Args;
+
+// `crawl [--sites <domain,...>] [--pages-per-site <n>] [--concurrency <n>]`. Ctrl+C stops the crawl,
+// keeping the pages indexed so far
+pub async fn crawl(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&["sites", "pages-per-site", "concurrency"])?;
+
+  let mut options = CrawlOptions::default();
+  if let Some(sites) = args.value("sites") {
+    options.sites = sites.split(',').map(|site| site.trim().to_lowercase()).filter(|site| !site.is_empty()).collect();
+    if let Some(unknown) = options.sites.iter().find(|site| !config::SITES.contains(&site.as_str())) {
+      bail!("`{}` isn't one of the configured sites ( config::SITES )", unknown);
+    }
+  }
+  if let Some(pages_per_site) = args.parsed("pages-per-site")? {
+    options.pages_per_site = pages_per_site;
+  }
+  if let Some(concurrency) = args.parsed("concurrency")? {
+    options.max_concurrent_requests = concurrency;
+  }
+
+  let cancel = CancellationToken::new();
+  let on_ctrl_c = cancel.clone();
+  tokio::spawn(async move {
+    if tokio::signal::ctrl_c().await.is_ok() {
+      eprintln!("Stopping the crawl, waiting for the pages indexed so far to be committed");
+      on_ctrl_c.cancel();
+    }
+  });
+
+  crawler::start_indexing(&args.index_path(), &options, cancel).await?;
+  Ok(())
+}
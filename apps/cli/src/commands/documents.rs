@@ -0,0 +1,43 @@
+use anyhow::bail;
+use crawler::DocumentSelector;
+
+use crate::args::Args;
+
+fn selector(args: &Args) -> anyhow::Result<DocumentSelector> {
+  match (args.value("url"), args.value("domain")) {
+    (Some(url), None) if !url.trim().is_empty() => Ok(DocumentSelector::Url(url.trim().to_string())),
+    (None, Some(domain)) if !domain.trim().is_empty() => Ok(DocumentSelector::Domain(domain.trim().to_string())),
+    _ => bail!("Expected either `--url` or `--domain`"),
+  }
+}
+
+// `delete --url <url>` or `delete --domain <domain>`
+pub fn delete(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&["url", "domain"])?;
+  let selector = selector(&args)?;
+
+  let deleted = crawler::delete_documents(&args.index_path(), &selector)?;
+  println!("Deleted {} pages", deleted);
+  Ok(())
+}
+
+// `inspect --url <url>`, every stored field of the page
+pub fn inspect(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&["url"])?;
+  let Some(url) = args.value("url") else {
+    bail!("Missing `--url`");
+  };
+
+  let index = schema::open_index(args.current_index()?)?;
+  let documents = crawler::find_documents(&index, &DocumentSelector::Url(url.to_string()))?;
+  if documents.is_empty() {
+    bail!("{} isn't indexed", url);
+  }
+
+  let schema = index.schema();
+  for doc in documents {
+    let fields: serde_json::Value = serde_json::from_str(&schema.to_json(&doc))?;
+    println!("{}", serde_json::to_string_pretty(&fields)?);
+  }
+  Ok(())
+}
@@ -0,0 +1,93 @@
+use std::{fs::File, io::{self, BufReader, BufWriter}, path::Path};
+
+use anyhow::bail;
+use crawler::ImportReport;
+
+use crate::args::Args;
+
+// `export [--output <file>]`, every stored page as JSON lines. Writes to stdout without `--output`
+pub fn export(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&["output"])?;
+  let index = schema::open_index(args.current_index()?)?;
+
+  let exported = match args.value("output") {
+    Some(path) => crawler::export_documents(&index, BufWriter::new(File::create(path)?))?,
+    None => crawler::export_documents(&index, BufWriter::new(io::stdout().lock()))?,
+  };
+  eprintln!("Exported {} pages", exported);
+  Ok(())
+}
+
+// `import <file> [--merge]`, pages exported with `export`. `-` reads from stdin. The pages are imported
+// into a new snapshot, which only holds the imported pages unless `--merge` is given: then it starts
+// from the current pages, replacing the ones with the same url. The previous snapshot is kept for rollback
+pub fn import(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&[])?;
+  let [path] = &args.positional[..] else {
+    bail!("Expected the file to import");
+  };
+
+  let snapshots = args.snapshots();
+  let staging = snapshots.stage(args.switch("merge"))?;
+  let report = match import_into(staging.path(), path) {
+    Ok(report) => report,
+    Err(e) => {
+      snapshots.discard(staging)?;
+      return Err(e);
+    },
+  };
+  let snapshot = snapshots.publish(staging, config::INDEX_SNAPSHOTS_KEPT)?;
+
+  println!("Imported {} pages into snapshot {}", report.imported, snapshot);
+  for (field, count) in &report.dropped_fields {
+    println!("Dropped `{}` of {} pages, the schema doesn't have it", field, count);
+  }
+  for (field, count) in &report.derived_fields {
+    println!("Derived `{}` of {} pages", field, count);
+  }
+  Ok(())
+}
+
+fn import_into(staging: &Path, path: &str) -> anyhow::Result<ImportReport> {
+  let index = schema::open_or_create_index(staging)?;
+  Ok(match path {
+    "-" => crawler::import_documents(&index, io::stdin().lock())?,
+    path => crawler::import_documents(&index, BufReader::new(File::open(path)?))?,
+  })
+}
+
+// `snapshots`, the published snapshots of the index, oldest first
+pub fn snapshots(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&[])?;
+  let snapshots = args.snapshots();
+  let current = snapshots.current_id()?;
+
+  let ids = snapshots.list()?;
+  if ids.is_empty() {
+    println!("No snapshots published in {}", snapshots.root().display());
+  }
+  for id in ids {
+    let marker = if Some(&id) == current.as_ref() { "  ( current )" } else { "" };
+    println!("{}{}", id, marker);
+  }
+  Ok(())
+}
+
+// `rollback [--to <snapshot>]`, serves an older snapshot, by default the one published before the current one
+pub fn rollback(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&["to"])?;
+  let snapshot = args.snapshots().rollback(args.value("to"))?;
+  println!("Serving snapshot {}", snapshot);
+  Ok(())
+}
+
+// `compact`, merges the index's segments and drops the data of deleted pages
+pub fn compact(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&[])?;
+  let stats = crawler::compact_index(&args.index_path())?;
+  println!(
+    "Merged {} segments into {}, dropped {} deleted pages",
+    stats.segments_before, stats.segments_after, stats.deleted_docs_removed
+  );
+  Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod crawl;
+pub mod documents;
+pub mod maintenance;
+pub mod search;
+pub mod stats;
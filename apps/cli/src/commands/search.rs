@@ -0,0 +1,49 @@
+use anyhow::bail;
+use searcher::{DocSearcher, SearchOptions};
+
+use crate::args::Args;
+
+// `search <query> [--limit <n>] [--lang <ecosystem>] [--version <version>] [--explain]`. Any other
+// `--<option> <value>` overrides a ranking option, named like the search API parameters
+pub fn search(args: Args) -> anyhow::Result<()> {
+  let query = args.positional.join(" ");
+  if query.trim().is_empty() {
+    bail!("Missing query");
+  }
+
+  let mut options = SearchOptions::default();
+  for (name, value) in &args.options {
+    match name.as_str() {
+      "index" => {},
+      "lang" => options.lang = Some(value.to_lowercase()),
+      "version" => options.version = Some(value.clone()),
+      _ => options.apply_override(name, value).map_err(anyhow::Error::msg)?,
+    }
+  }
+  options.explain = args.switch("explain");
+
+  let doc_searcher = DocSearcher::new(args.current_index()?.to_string_lossy().into_owned())?;
+  let results = doc_searcher.search(&query, &options)?;
+
+  for expansion in &results.expansions {
+    println!("Also searched {} for {}", expansion.alternatives.join(", "), expansion.term);
+  }
+  if results.results.is_empty() {
+    println!("No results");
+  }
+  for (rank, result) in results.results.iter().enumerate() {
+    let heading = if result.heading.is_empty() { String::new() } else { format!(" > {}", result.heading) };
+    let version = result.version.as_ref().map(|version| format!(" ( {} )", version)).unwrap_or_default();
+    println!("{:>3}. {}{}{}", rank + 1, result.title, heading, version);
+    println!("     {}", result.url);
+    println!("     {:.3}  {}", result.score, result.content_snippet);
+    if let Some(explanation) = &result.explanation {
+      println!("{}", explanation.to_pretty_json());
+    }
+  }
+  if let Some(parsed_query) = &results.parsed_query {
+    println!("Parsed query: {}", parsed_query);
+  }
+
+  Ok(())
+}
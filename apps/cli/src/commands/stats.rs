@@ -0,0 +1,37 @@
+use searcher::DocSearcher;
+
+use crate::args::Args;
+
+// `stats`, size of the index and the number of pages of every crawled site
+pub fn stats(args: Args) -> anyhow::Result<()> {
+  args.expect_options(&[])?;
+  let index_path = args.current_index()?;
+
+  let index = schema::open_index(&index_path)?;
+  let segments = index.searchable_segment_metas()?;
+  let deleted_docs: u32 = segments.iter().map(|segment| segment.num_deleted_docs()).sum();
+  let disk_bytes: u64 = std::fs::read_dir(&index_path)?
+    .filter_map(|entry| entry.ok()?.metadata().ok())
+    .map(|metadata| metadata.len())
+    .sum();
+
+  let doc_searcher = DocSearcher::new(index_path.to_string_lossy().into_owned())?;
+  println!("Index: {}", index_path.display());
+  if let Some(snapshot) = args.snapshots().current_id()? {
+    println!("Snapshot: {}", snapshot);
+  }
+  println!("Pages: {}", doc_searcher.num_docs());
+  println!("Deleted pages not compacted yet: {}", deleted_docs);
+  println!("Segments: {}", segments.len());
+  println!("Size on disk: {:.1} MB", disk_bytes as f64 / (1024.0 * 1024.0));
+
+  println!();
+  let mut pages_per_site = doc_searcher.get_pages_per_site()?;
+  pages_per_site.retain(|(_, count)| *count > 0);
+  pages_per_site.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+  for (site, count) in pages_per_site {
+    println!("{:>8}  {}", count, site);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,61 @@
+use args::Args;
+use commands::{crawl::crawl, documents::{delete, inspect}, maintenance::{compact, export, import, rollback, snapshots}, search::search, stats::stats};
+
+mod args;
+mod commands;
+
+const USAGE: &str = "Usage: mini-search <command> [--index <path>] [options]
+
+Commands:
+  crawl [--sites <domain,...>] [--pages-per-site <n>] [--concurrency <n>]
+  search <query> [--limit <n>] [--lang <ecosystem>] [--version <version>] [--explain] [--<ranking option> <value>]
+  stats
+  delete --url <url> | --domain <domain>
+  inspect --url <url>
+  export [--output <file>]
+  import <file | -> [--merge]
+  compact
+  snapshots
+  rollback [--to <snapshot>]
+
+`--index` defaults to config::INDEX_PATH. Logs, API keys and link graph paths come from the config crate";
+
+#[tokio::main]
+async fn main() {
+  let mut args = std::env::args().skip(1);
+  let command = args.next().unwrap_or_default();
+  if matches!(command.as_str(), "" | "help" | "--help" | "-h") {
+    println!("{}", USAGE);
+    return;
+  }
+
+  let switches: &[&str] = match command.as_str() {
+    "search" => &["explain"],
+    "import" => &["merge"],
+    _ => &[],
+  };
+  let result = match Args::parse(args, switches) {
+    Ok(args) => match command.as_str() {
+      "crawl" => crawl(args).await,
+      "search" => search(args),
+      "stats" => stats(args),
+      "delete" => delete(args),
+      "inspect" => inspect(args),
+      "export" => export(args),
+      "import" => import(args),
+      "compact" => compact(args),
+      "snapshots" => snapshots(args),
+      "rollback" => rollback(args),
+      _ => {
+        eprintln!("Unknown command `{}`\n\n{}", command, USAGE);
+        std::process::exit(2);
+      },
+    },
+    Err(e) => Err(e),
+  };
+
+  if let Err(e) = result {
+    eprintln!("Error: {:#}", e);
+    std::process::exit(1);
+  }
+}
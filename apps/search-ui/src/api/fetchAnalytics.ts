@@ -2,7 +2,7 @@ import axios from "axios";
 import { AnalyticsEntry } from "../types/AnalyticsEntry";
 
 const fetchAnalytics = async (): Promise<AnalyticsEntry[]> => {
-  const response = await axios.get(`api/analytics`);
+  const response = await axios.get(`api/v1/analytics`);
   const data = await response.data;
   return data;
 }
@@ -1,7 +1,7 @@
 import axios from "axios";
 
 const fetchCrawledSites = async (domain?: string, limit?: number, offset?: number): Promise<{ urls: string[], total: number }> => {
-  const response = await axios.get('api/crawled_urls', {
+  const response = await axios.get('api/v1/crawled_urls', {
     params: {
       ...(domain && { domain }),
       ...(limit && { limit }),
@@ -2,7 +2,7 @@ import axios from "axios";
 import { SearchResult } from "../types/Search";
 
 const fetchSearchResults = async (query: string): Promise<{ results: SearchResult[], took_ms: number}> => {
-  const response = await axios.get(`api/search?q=${query}`);
+  const response = await axios.get(`api/v1/search?q=${query}`);
   const data = await response.data;
   return data;
 }
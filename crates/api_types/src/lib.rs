@@ -0,0 +1,7 @@
+// Request and response types of the API, shared by the server, its OpenAPI document and the client
+#[macro_use]
+pub mod schema;
+
+pub mod v1;
+
+pub use schema::{ApiField, ApiObject, ApiSchema};
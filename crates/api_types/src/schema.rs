@@ -0,0 +1,150 @@
+use serde_json::{json, Map, Value};
+
+// OpenAPI 3 schema of a type sent or received by the API
+pub trait ApiSchema {
+  // Schema where the type is used, a `$ref` into `components/schemas` for the types defined with `api_type!`
+  fn schema() -> Value;
+
+  // Whether a field of this type must be present, false for `Option`
+  fn required() -> bool {
+    true
+  }
+
+  // Adds the schemas of the named types this type refers to, including itself, to `components`
+  fn register(_components: &mut Map<String, Value>) {}
+}
+
+// Field of a type defined with `api_type!`
+#[derive(Debug, Clone)]
+pub struct ApiField {
+  pub name: &'static str,
+  pub schema: Value,
+  pub required: bool,
+}
+
+// Types defined with `api_type!`, whose fields are also used as query parameters
+pub trait ApiObject: ApiSchema {
+  fn fields() -> Vec<ApiField>;
+}
+
+// Defines a struct and implements `ApiSchema` / `ApiObject` from its fields, so the OpenAPI schema
+// can't drift from what serde sends. Fields must be `pub` and keep their serde names
+macro_rules! api_type {
+  (
+    $(#[$meta:meta])*
+    pub struct $name:ident {
+      $(
+        $(#[$field_meta:meta])*
+        pub $field:ident: $ty:ty,
+      )*
+    }
+  ) => {
+    $(#[$meta])*
+    pub struct $name {
+      $(
+        $(#[$field_meta])*
+        pub $field: $ty,
+      )*
+    }
+
+    impl $crate::ApiSchema for $name {
+      fn schema() -> serde_json::Value {
+        serde_json::json!({ "$ref": concat!("#/components/schemas/", stringify!($name)) })
+      }
+
+      fn register(components: &mut serde_json::Map<String, serde_json::Value>) {
+        if components.contains_key(stringify!($name)) {
+          return;
+        }
+        // Placeholder, so recursive types stop here
+        components.insert(stringify!($name).to_string(), serde_json::Value::Null);
+        $( <$ty as $crate::ApiSchema>::register(components); )*
+        components.insert(stringify!($name).to_string(), $crate::schema::object_schema(&<$name as $crate::ApiObject>::fields()));
+      }
+    }
+
+    impl $crate::ApiObject for $name {
+      fn fields() -> Vec<$crate::ApiField> {
+        vec![
+          $(
+            $crate::ApiField {
+              name: stringify!($field),
+              schema: <$ty as $crate::ApiSchema>::schema(),
+              required: <$ty as $crate::ApiSchema>::required(),
+            },
+          )*
+        ]
+      }
+    }
+  };
+}
+
+pub fn object_schema(fields: &[ApiField]) -> Value {
+  let properties: Map<String, Value> = fields.iter()
+    .map(|field| (field.name.to_string(), field.schema.clone()))
+    .collect();
+  let required: Vec<&str> = fields.iter()
+    .filter(|field| field.required)
+    .map(|field| field.name)
+    .collect();
+
+  json!({
+    "type": "object",
+    "properties": properties,
+    "required": required,
+  })
+}
+
+macro_rules! primitive_schema {
+  ($($ty:ty => $schema:tt),* $(,)?) => {
+    $(
+      impl ApiSchema for $ty {
+        fn schema() -> Value {
+          json!($schema)
+        }
+      }
+    )*
+  };
+}
+
+primitive_schema! {
+  String => { "type": "string" },
+  bool => { "type": "boolean" },
+  u32 => { "type": "integer", "format": "int32", "minimum": 0 },
+  u64 => { "type": "integer", "format": "int64", "minimum": 0 },
+  usize => { "type": "integer", "format": "int64", "minimum": 0 },
+  i64 => { "type": "integer", "format": "int64" },
+  f32 => { "type": "number", "format": "float" },
+  f64 => { "type": "number", "format": "double" },
+}
+
+impl<T: ApiSchema> ApiSchema for Vec<T> {
+  fn schema() -> Value {
+    json!({ "type": "array", "items": T::schema() })
+  }
+
+  fn register(components: &mut Map<String, Value>) {
+    T::register(components);
+  }
+}
+
+// Missing or null. OpenAPI 3.0 doesn't allow `nullable` next to a `$ref`, so references are wrapped
+impl<T: ApiSchema> ApiSchema for Option<T> {
+  fn schema() -> Value {
+    match T::schema() {
+      Value::Object(mut schema) if !schema.contains_key("$ref") => {
+        schema.insert("nullable".to_string(), Value::Bool(true));
+        Value::Object(schema)
+      },
+      schema => json!({ "allOf": [schema], "nullable": true }),
+    }
+  }
+
+  fn required() -> bool {
+    false
+  }
+
+  fn register(components: &mut Map<String, Value>) {
+    T::register(components);
+  }
+}
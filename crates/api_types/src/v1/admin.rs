@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct MessageResponse {
+    pub message: String,
+  }
+}
+
+api_type! {
+  // Query parameters of `DELETE /api/v1/documents`, exactly one of them must be set
+  #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+  pub struct DeleteDocumentsQuery {
+    // Delete the page with this url, query and fragment are ignored
+    pub url: Option<String>,
+    // Delete every page of this domain
+    pub domain: Option<String>,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct DeleteDocumentsResponse {
+    pub deleted: usize,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct ReloadConfigResponse {
+    pub message: String,
+    pub api_keys: usize,
+  }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct PagesPerSiteEntry {
+    pub domain: String,
+    pub count: usize,
+  }
+}
+
+api_type! {
+  // Query parameters of `/api/v1/crawled_urls`
+  #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+  pub struct ScrapedUrlsQuery {
+    pub domain: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct ScrapedUrlsResponse {
+    pub urls: Vec<String>,
+    pub total: usize,
+  }
+}
+
+api_type! {
+  // Query parameters of the `/api/v1/analytics/*` search analytics
+  #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+  pub struct AnalyticsQuery {
+    // Only count searches at or after this date, as `2024-05-01` or RFC 3339
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+    // Bucket size of the query volume, `hour` or `day` ( default )
+    pub interval: Option<String>,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct QueryCount {
+    pub query: String,
+    pub count: usize,
+    // Epoch milliseconds of the latest search
+    pub last_seen: i64,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct LatencyPercentiles {
+    pub count: usize,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct VolumeBucket {
+    // Epoch milliseconds of the start of the bucket
+    pub start: i64,
+    pub count: usize,
+  }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+api_type! {
+  // Body of `/api/v1/click`
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct ClickRequest {
+    // `query_id` returned by `/api/v1/search`
+    pub query_id: String,
+    pub url: String,
+    // 1 based position of the result on the results page
+    pub position: usize,
+  }
+}
+
+api_type! {
+  // Query parameters of `/api/v1/go`
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct GoQuery {
+    pub u: String,
+    pub qid: String,
+    pub pos: usize,
+  }
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+api_type! {
+  // Body of every error response
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct ErrorResponse {
+    pub error: String,
+    // Stable machine-readable code, like `invalid_query` or `rate_limited`
+    pub code: String,
+    // Also sent as `X-Request-Id`, for finding the request in the logs
+    pub request_id: Option<String>,
+  }
+}
@@ -0,0 +1,15 @@
+// Types of the `/api/v1` routes. Their JSON shapes are pinned by the contract tests of the API, a
+// breaking change to them needs a new version module and routes instead
+mod admin;
+mod analytics;
+mod clicks;
+mod error;
+mod search;
+mod stats;
+
+pub use admin::{DeleteDocumentsQuery, DeleteDocumentsResponse, MessageResponse, ReloadConfigResponse};
+pub use analytics::{AnalyticsQuery, LatencyPercentiles, PagesPerSiteEntry, QueryCount, ScrapedUrlsQuery, ScrapedUrlsResponse, VolumeBucket};
+pub use clicks::{ClickRequest, GoQuery};
+pub use error::ErrorResponse;
+pub use search::{QueryExpansion, ScoreExplanation, SearchQuery, SearchResponse, SearchResult};
+pub use stats::{CacheStats, StatsResponse};
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+api_type! {
+  // Query parameters of `/api/v1/search`
+  #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+  pub struct SearchQuery {
+    pub q: String,
+    // Restrict results to an ecosystem, same as `lang:<ecosystem>` in `q`
+    pub lang: Option<String>,
+    // Boost results from an ecosystem, defaults to `config::PREFERRED_ECOSYSTEM`
+    pub prefer: Option<String>,
+    // Restrict results to a documentation version, same as `version:<version>` in `q`
+    pub version: Option<String>,
+    // Set to false to disable synonym expansion
+    pub synonyms: Option<bool>,
+    // `relevance` ( default ) or `date`
+    pub sort: Option<String>,
+    // Last modified date range, as `2024-05-01` or RFC 3339
+    pub after: Option<String>,
+    pub before: Option<String>,
+    // Freshness ranking overrides, see `config::FRESHNESS_WEIGHT`
+    pub freshness: Option<f32>,
+    pub half_life_days: Option<f32>,
+    pub authority: Option<f32>,
+    // Results per site before other sites' results, 0 disables the cap. See `config::MAX_RESULTS_PER_SITE`
+    pub max_per_site: Option<usize>,
+    // Per-site score multipliers overriding `config::SITE_BOOSTS`, as `css-tricks.com:1,forum.crystal-lang.org:0.5`
+    pub site_boost: Option<String>,
+    // Query tuning overrides, see `config::FIELD_BOOSTS`. `fields` restricts the searched fields as
+    // `title,headings`, `boost` sets field boosts as `title:5,anchors:1`
+    pub fields: Option<String>,
+    pub boost: Option<String>,
+    // `or` ( default ) or `and`, whether any or all of the query terms must match
+    pub operator: Option<String>,
+    pub phrase_boost: Option<f32>,
+    pub slop: Option<u32>,
+    // Return the score explanation of every result and the parsed query
+    pub explain: Option<bool>,
+    // Weight of the click-through priors, see `config::CLICK_WEIGHT`
+    pub clicks: Option<f32>,
+  }
+}
+
+api_type! {
+  // Tantivy's explanation of a result's score, a tree of the scores it was computed from
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct ScoreExplanation {
+    pub value: f32,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<ScoreExplanation>>,
+    pub context: Vec<String>,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct SearchResult {
+    pub title: String,
+    pub heading: String,
+    pub url: String,
+    pub snippet: String,
+    pub score: f32,
+    pub scraped_at: i64,
+    pub last_modified: i64,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub other_versions: Vec<String>,
+    pub also_at: Vec<String>,
+    pub more_from_site: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreExplanation>,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct QueryExpansion {
+    pub term: String,
+    pub alternatives: Vec<String>,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct SearchResponse {
+    // Identifies the search when reporting clicks to `/api/v1/click` or `/api/v1/go`
+    pub query_id: String,
+    pub results: Vec<SearchResult>,
+    pub expansions: Vec<QueryExpansion>,
+    pub total: usize,
+    pub query: String,
+    pub took_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parsed_query: Option<String>,
+  }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    // Share of searches served from the cache, 0 before the first search
+    pub hit_rate: f64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub capacity_bytes: usize,
+  }
+}
+
+api_type! {
+  #[derive(Debug, Clone, Serialize, Deserialize)]
+  pub struct StatsResponse {
+    pub num_docs: u64,
+    pub cache: CacheStats,
+  }
+}
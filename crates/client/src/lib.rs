@@ -0,0 +1,153 @@
+// Typed async client of the API
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use api_types::v1::*;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+  #[error(transparent)]
+  Http(#[from] reqwest::Error),
+  // Error response of the API, `code` is one of the codes listed in the README
+  #[error("{message} ( {status} {code} )")]
+  Api {
+    status: StatusCode,
+    code: String,
+    message: String,
+    request_id: Option<String>,
+  },
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+  http: reqwest::Client,
+  base_url: String,
+  api_key: Option<String>,
+}
+
+impl Client {
+  // `base_url` is where the API is served, like `http://localhost:8000`. The client uses the `/api/v1` routes
+  pub fn new(base_url: impl Into<String>) -> Self {
+    Self::with_http_client(reqwest::Client::new(), base_url)
+  }
+
+  pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+    let base_url = base_url.into().trim_end_matches('/').to_string();
+    Self { http, base_url, api_key: None }
+  }
+
+  // Key sent as `Authorization: Bearer <key>`, required by the admin routes
+  pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+    self.api_key = Some(api_key.into());
+    self
+  }
+
+  fn request(&self, method: Method, path: &str) -> RequestBuilder {
+    let request = self.http.request(method, format!("{}{}", self.base_url, path));
+    match &self.api_key {
+      Some(api_key) => request.bearer_auth(api_key),
+      None => request,
+    }
+  }
+
+  // Turns error responses into `ClientError::Api`
+  async fn send(&self, request: RequestBuilder) -> Result<Response, ClientError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+      return Ok(response);
+    }
+
+    let request_id = response.headers().get("x-request-id")
+      .and_then(|value| value.to_str().ok())
+      .map(|id| id.to_string());
+    let text = response.text().await?;
+    Err(match serde_json::from_str::<ErrorResponse>(&text) {
+      Ok(body) => ClientError::Api { status, code: body.code, message: body.error, request_id: body.request_id.or(request_id) },
+      Err(_) => ClientError::Api { status, code: "unknown".to_string(), message: text, request_id },
+    })
+  }
+
+  async fn get<Q: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, query: &Q) -> Result<T, ClientError> {
+    let response = self.send(self.request(Method::GET, path).query(query)).await?;
+    Ok(response.json().await?)
+  }
+
+  async fn post<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+    let response = self.send(self.request(Method::POST, path)).await?;
+    Ok(response.json().await?)
+  }
+
+  pub async fn health(&self) -> Result<(), ClientError> {
+    self.send(self.request(Method::GET, "/api/v1/health")).await?;
+    Ok(())
+  }
+
+  pub async fn search(&self, query: &SearchQuery) -> Result<SearchResponse, ClientError> {
+    self.get("/api/v1/search", query).await
+  }
+
+  pub async fn click(&self, click: &ClickRequest) -> Result<(), ClientError> {
+    self.send(self.request(Method::POST, "/api/v1/click").json(click)).await?;
+    Ok(())
+  }
+
+  // Link to a result which records the click and redirects to it, for pages linking to results
+  pub fn go_url(&self, params: &GoQuery) -> Result<String, ClientError> {
+    let request = self.http.get(format!("{}/api/v1/go", self.base_url)).query(params).build()?;
+    Ok(request.url().to_string())
+  }
+
+  pub async fn pages_per_site(&self) -> Result<Vec<PagesPerSiteEntry>, ClientError> {
+    self.get("/api/v1/analytics", &()).await
+  }
+
+  pub async fn top_queries(&self, query: &AnalyticsQuery) -> Result<Vec<QueryCount>, ClientError> {
+    self.get("/api/v1/analytics/queries/top", query).await
+  }
+
+  pub async fn zero_result_queries(&self, query: &AnalyticsQuery) -> Result<Vec<QueryCount>, ClientError> {
+    self.get("/api/v1/analytics/queries/zero_results", query).await
+  }
+
+  pub async fn latency(&self, query: &AnalyticsQuery) -> Result<LatencyPercentiles, ClientError> {
+    self.get("/api/v1/analytics/latency", query).await
+  }
+
+  pub async fn query_volume(&self, query: &AnalyticsQuery) -> Result<Vec<VolumeBucket>, ClientError> {
+    self.get("/api/v1/analytics/volume", query).await
+  }
+
+  pub async fn crawled_urls(&self, query: &ScrapedUrlsQuery) -> Result<ScrapedUrlsResponse, ClientError> {
+    self.get("/api/v1/crawled_urls", query).await
+  }
+
+  pub async fn stats(&self) -> Result<StatsResponse, ClientError> {
+    self.get("/api/v1/stats", &()).await
+  }
+
+  pub async fn openapi(&self) -> Result<serde_json::Value, ClientError> {
+    self.get("/api/v1/openapi.json", &()).await
+  }
+
+  // Requires a key with the `crawl` scope
+  pub async fn trigger_indexing(&self) -> Result<MessageResponse, ClientError> {
+    self.post("/api/v1/index/trigger").await
+  }
+
+  // Requires a key with the `crawl` scope
+  pub async fn cancel_indexing(&self) -> Result<MessageResponse, ClientError> {
+    self.post("/api/v1/index/cancel").await
+  }
+
+  // Requires a key with the `delete` scope
+  pub async fn delete_documents(&self, query: &DeleteDocumentsQuery) -> Result<DeleteDocumentsResponse, ClientError> {
+    let response = self.send(self.request(Method::DELETE, "/api/v1/documents").query(query)).await?;
+    Ok(response.json().await?)
+  }
+
+  // Requires a key with the `config` scope
+  pub async fn reload_config(&self) -> Result<ReloadConfigResponse, ClientError> {
+    self.post("/api/v1/config/reload").await
+  }
+}
@@ -168,4 +168,411 @@ pub const SITES: [&str; 166] = [
 ];
 
 pub const NUM_PAGES_PER_SITE: i32 = 100;
+// Pages fetched at the same time across all sites, each site is still visited with a delay between requests
+pub const MAX_CONCURRENT_REQUESTS: usize = 10;
 pub const RE_CRAWL_DURATION: i64 = 1000 * 60 * 60 * 24; // 1 day
+
+// Search index written by the crawler and read by the API, relative to the working directory. Crawls
+// and imports build a new snapshot in `staging/` and publish it to `snapshots/`, `CURRENT` names the
+// snapshot being served
+pub const INDEX_PATH: &str = "./index";
+// Published snapshots kept for rollback, including the current one
+pub const INDEX_SNAPSHOTS_KEPT: usize = 3;
+// Largest page of indexed urls the API lists, and how far into them it can start. Collecting a page
+// holds `limit + offset` urls in memory
+pub const MAX_CRAWLED_URLS_LIMIT: usize = 100;
+pub const MAX_CRAWLED_URLS_OFFSET: usize = 10_000;
+// How often the API checks for a newly published ( or rolled back ) snapshot
+pub const SNAPSHOT_POLL_INTERVAL_SECS: u64 = 5;
+
+// Ecosystem ( language / platform ) each site in `SITES` belongs to.
+// Stored as the `lang` facet on every document, e.g. `/rust`
+pub const SITE_ECOSYSTEMS: [(&str, &str); 166] = [
+  ("angular.io", "javascript"),
+  ("api.drupal.org", "php"),
+  ("api.haxe.org", "haxe"),
+  ("api.qunitjs.com", "javascript"),
+  ("babeljs.io", "javascript"),
+  ("backbonejs.org", "javascript"),
+  ("bazel.build", "tools"),
+  ("bluebirdjs.com", "javascript"),
+  ("bower.io", "javascript"),
+  ("cfdocs.org", "coldfusion"),
+  ("clojure.org", "clojure"),
+  ("clojuredocs.org", "clojure"),
+  ("codecept.io", "javascript"),
+  ("codeception.com", "php"),
+  ("codeigniter.com", "php"),
+  ("coffeescript.org", "javascript"),
+  ("cran.r-project.org", "r"),
+  ("crystal-lang.org", "crystal"),
+  ("forum.crystal-lang.org", "crystal"),
+  ("css-tricks.com", "css"),
+  ("dart.dev", "dart"),
+  ("dev.mysql.com", "sql"),
+  ("developer.apple.com", "swift"),
+  ("developer.mozilla.org", "javascript"),
+  ("developer.wordpress.org", "php"),
+  ("doc.deno.land", "javascript"),
+  ("doc.rust-lang.org", "rust"),
+  ("docs.astro.build", "javascript"),
+  ("docs.aws.amazon.com", "cloud"),
+  ("docs.brew.sh", "tools"),
+  ("docs.chef.io", "devops"),
+  ("docs.cypress.io", "javascript"),
+  ("docs.influxdata.com", "database"),
+  ("docs.julialang.org", "julia"),
+  ("docs.microsoft.com", "dotnet"),
+  ("docs.npmjs.com", "javascript"),
+  ("docs.oracle.com", "java"),
+  ("docs.phalconphp.com", "php"),
+  ("docs.python.org", "python"),
+  ("docs.rs", "rust"),
+  ("docs.ruby-lang.org", "ruby"),
+  ("docs.saltproject.io", "devops"),
+  ("docs.wagtail.org", "python"),
+  ("doctrine-project.org", "php"),
+  ("docwiki.embarcadero.com", "delphi"),
+  ("eigen.tuxfamily.org", "cpp"),
+  ("elixir-lang.org", "elixir"),
+  ("elm-lang.org", "elm"),
+  ("en.cppreference.com", "cpp"),
+  ("enzymejs.github.io", "javascript"),
+  ("erights.org", "e"),
+  ("erlang.org", "erlang"),
+  ("esbuild.github.io", "javascript"),
+  ("eslint.org", "javascript"),
+  ("expressjs.com", "javascript"),
+  ("fastapi.tiangolo.com", "python"),
+  ("flow.org", "javascript"),
+  ("fortran90.org", "fortran"),
+  ("fsharp.org", "dotnet"),
+  ("getbootstrap.com", "css"),
+  ("getcomposer.org", "php"),
+  ("git-scm.com", "tools"),
+  ("gnu.org", "linux"),
+  ("gnucobol.sourceforge.io", "cobol"),
+  ("go.dev", "go"),
+  ("golang.org", "go"),
+  ("graphite.readthedocs.io", "python"),
+  ("groovy-lang.org", "groovy"),
+  ("gruntjs.com", "javascript"),
+  ("handlebarsjs.com", "javascript"),
+  ("haskell.org", "haskell"),
+  ("hex.pm", "elixir"),
+  ("hexdocs.pm", "elixir"),
+  ("httpd.apache.org", "devops"),
+  ("i3wm.org", "linux"),
+  ("jasmine.github.io", "javascript"),
+  ("javascript.info", "javascript"),
+  ("jekyllrb.com", "ruby"),
+  ("jsdoc.app", "javascript"),
+  ("julialang.org", "julia"),
+  ("knockoutjs.com", "javascript"),
+  ("kotlinlang.org", "kotlin"),
+  ("laravel.com", "php"),
+  ("latexref.xyz", "latex"),
+  ("learn.microsoft.com", "dotnet"),
+  ("lesscss.org", "css"),
+  ("love2d.org", "lua"),
+  ("lua.org", "lua"),
+  ("man7.org", "linux"),
+  ("mariadb.com", "sql"),
+  ("mochajs.org", "javascript"),
+  ("modernizr.com", "javascript"),
+  ("momentjs.com", "javascript"),
+  ("mongoosejs.com", "javascript"),
+  ("next.router.vuejs.org", "javascript"),
+  ("next.vuex.vuejs.org", "javascript"),
+  ("nginx.org", "devops"),
+  ("nim-lang.org", "nim"),
+  ("nixos.org", "devops"),
+  ("nodejs.org", "javascript"),
+  ("npmjs.com", "javascript"),
+  ("ocaml.org", "ocaml"),
+  ("odin-lang.org", "odin"),
+  ("openjdk.java.net", "java"),
+  ("opentsdb.net", "database"),
+  ("perldoc.perl.org", "perl"),
+  ("php.net", "php"),
+  ("playwright.dev", "javascript"),
+  ("pointclouds.org", "cpp"),
+  ("postgresql.org", "sql"),
+  ("prettier.io", "javascript"),
+  ("pugjs.org", "javascript"),
+  ("pydata.org", "python"),
+  ("pytorch.org", "python"),
+  ("qt.io", "cpp"),
+  ("r-project.org", "r"),
+  ("react-bootstrap.github.io", "javascript"),
+  ("reactivex.io", "javascript"),
+  ("reactjs.org", "javascript"),
+  ("reactnative.dev", "javascript"),
+  ("reactrouterdotcom.fly.dev", "javascript"),
+  ("readthedocs.io", "python"),
+  ("readthedocs.org", "python"),
+  ("redis.io", "database"),
+  ("redux.js.org", "javascript"),
+  ("requirejs.org", "javascript"),
+  ("rethinkdb.com", "database"),
+  ("ruby-doc.org", "ruby"),
+  ("ruby-lang.org", "ruby"),
+  ("rust-lang.org", "rust"),
+  ("rxjs.dev", "javascript"),
+  ("sass-lang.com", "css"),
+  ("scala-lang.org", "scala"),
+  ("scikit-image.org", "python"),
+  ("scikit-learn.org", "python"),
+  ("spring.io", "java"),
+  ("sqlite.org", "sql"),
+  ("stdlib.ponylang.io", "pony"),
+  ("superuser.com", "tools"),
+  ("svelte.dev", "javascript"),
+  ("swift.org", "swift"),
+  ("tailwindcss.com", "css"),
+  ("twig.symfony.com", "php"),
+  ("typescriptlang.org", "javascript"),
+  ("underscorejs.org", "javascript"),
+  ("vitejs.dev", "javascript"),
+  ("vitest.dev", "javascript"),
+  ("vuejs.org", "javascript"),
+  ("vueuse.org", "javascript"),
+  ("webpack.js.org", "javascript"),
+  ("wiki.archlinux.org", "linux"),
+  ("www.chaijs.com", "javascript"),
+  ("www.electronjs.org", "javascript"),
+  ("www.gnu.org", "linux"),
+  ("www.hammerspoon.org", "lua"),
+  ("www.khronos.org", "graphics"),
+  ("www.lua.org", "lua"),
+  ("www.php.net/manual/en/", "php"),
+  ("www.pygame.org", "python"),
+  ("www.rubydoc.info", "ruby"),
+  ("www.statsmodels.org", "python"),
+  ("www.tcl.tk", "tcl"),
+  ("www.terraform.io", "devops"),
+  ("www.vagrantup.com", "devops"),
+  ("www.yiiframework.com", "php"),
+  ("yarnpkg.com", "javascript")
+];
+
+// Ecosystem whose results are boosted ( not filtered ) when the request doesn't specify one
+pub const PREFERRED_ECOSYSTEM: Option<&str> = None;
+pub const PREFERRED_ECOSYSTEM_BOOST: f32 = 1.5;
+
+// Host of a `SITES` entry, which can also have a path
+fn site_host(site: &'static str) -> &'static str {
+  site.split('/').next().unwrap_or(site)
+}
+
+fn is_subdomain_of(domain: &str, host: &str) -> bool {
+  domain.strip_suffix(host).is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+// Returns the ecosystem for a crawled domain. Subdomains of a configured site
+// ( e.g. `graphite.readthedocs.io` ) fall back to the parent site's ecosystem
+pub fn ecosystem_for_domain(domain: &str) -> Option<&'static str> {
+  SITE_ECOSYSTEMS.iter()
+    .find(|(site, _)| site_host(site) == domain)
+    .or_else(|| SITE_ECOSYSTEMS.iter().find(|(site, _)| is_subdomain_of(domain, site_host(site))))
+    .map(|(_, ecosystem)| *ecosystem)
+}
+
+// Site ( host of a `SITES` entry ) a page belongs to, subdomains belong to their parent site
+pub fn site_for_domain(domain: &str) -> Option<&'static str> {
+  SITES.iter()
+    .map(|site| site_host(site))
+    .find(|site| *site == domain)
+    .or_else(|| SITES.iter().map(|site| site_host(site)).find(|site| is_subdomain_of(domain, site)))
+}
+
+// Score multiplier per site: official references rank above forums and tutorial aggregators.
+// Sites which aren't listed keep their score, boosts can be overridden per request
+pub const SITE_BOOSTS: [(&str, f32); 15] = [
+  ("developer.mozilla.org", 1.3),
+  ("doc.rust-lang.org", 1.3),
+  ("docs.python.org", 1.3),
+  ("docs.rs", 1.2),
+  ("en.cppreference.com", 1.2),
+  ("go.dev", 1.2),
+  ("nodejs.org", 1.2),
+  ("php.net", 1.2),
+  ("postgresql.org", 1.2),
+  ("typescriptlang.org", 1.2),
+  ("www.php.net", 1.2),
+  ("javascript.info", 0.9),
+  ("css-tricks.com", 0.8),
+  ("superuser.com", 0.7),
+  ("forum.crystal-lang.org", 0.6),
+];
+
+// Per-site patterns used to detect documentation versions in the url path, as ( site, product, pattern ).
+// A pattern must have a `version` capture group and may have a `product` group which overrides the product.
+// An empty product means the page's domain. Subdomains of a site use the site's pattern
+pub const VERSION_PATTERNS: [(&str, &str, &str); 17] = [
+  ("angular.io", "angular", r"^/(?P<version>v\d+)/"),
+  ("dev.mysql.com", "mysql", r"^/doc/refman/(?P<version>\d+\.\d+)/"),
+  ("doc.rust-lang.org", "rust", r"^/(?P<version>\d+\.\d+\.\d+|stable|beta|nightly)/"),
+  ("docs.julialang.org", "julia", r"^/en/(?P<version>v\d+(?:\.\d+)*|stable)/"),
+  ("docs.oracle.com", "java", r"^/(?:en/java/)?javase/(?P<version>\d+)/"),
+  ("docs.python.org", "python", r"^/(?P<version>\d+(?:\.\d+)*)/"),
+  ("docs.rs", "", r"^/(?:crate/)?(?P<product>[^/]+)/(?P<version>\d+\.\d+\.\d+[^/]*|latest)/"),
+  ("docs.ruby-lang.org", "ruby", r"^/en/(?P<version>\d+\.\d+|master)/"),
+  ("hexdocs.pm", "", r"^/(?P<product>[^/]+)/(?P<version>\d+\.\d+\.\d+[^/]*)/"),
+  ("nodejs.org", "node", r"^/docs/(?P<version>v\d+\.\d+\.\d+|latest(?:-v\d+\.x)?)/"),
+  ("postgresql.org", "postgresql", r"^/docs/(?P<version>\d+(?:\.\d+)?|current)/"),
+  ("readthedocs.io", "", r"^/(?:[a-z]{2}/)?(?P<version>v?\d+(?:\.\d+)*|stable|latest)/"),
+  ("ruby-doc.org", "ruby", r"^/(?:core-|stdlib-)?(?P<version>\d+\.\d+(?:\.\d+)?)/"),
+  ("scikit-image.org", "scikit-image", r"^/docs/(?P<version>\d+\.\d+(?:\.x)?|stable|dev)/"),
+  ("scikit-learn.org", "scikit-learn", r"^/(?P<version>\d+\.\d+|stable|dev)/"),
+  ("www.postgresql.org", "postgresql", r"^/docs/(?P<version>\d+(?:\.\d+)?|current)/"),
+  ("www.statsmodels.org", "statsmodels", r"^/(?P<version>v?\d+\.\d+(?:\.\d+)?|stable|dev)/"),
+];
+
+// Number of candidates fetched per requested result, so versions / duplicates can be collapsed
+// without losing results
+pub const COLLAPSE_CANDIDATES_FACTOR: usize = 5;
+// Most results a single search can return
+pub const MAX_SEARCH_LIMIT: usize = 1000;
+
+// Built-in synonyms and abbreviations expanded at query time, as ( term, alternatives ).
+// Entries from `SYNONYMS_PATH` are added on top and replace built-in entries for the same term
+pub const SYNONYMS: [(&str, &[&str]); 29] = [
+  ("args", &["arguments"]),
+  ("async", &["asynchronous"]),
+  ("auth", &["authentication", "authorization"]),
+  ("cli", &["command line"]),
+  ("config", &["configuration"]),
+  ("cpp", &["c++"]),
+  ("db", &["database"]),
+  ("dir", &["directory"]),
+  ("docs", &["documentation"]),
+  ("env", &["environment"]),
+  ("env var", &["environment variable"]),
+  ("fn", &["function"]),
+  ("func", &["function"]),
+  ("golang", &["go"]),
+  ("js", &["javascript"]),
+  ("k8s", &["kubernetes"]),
+  ("lib", &["library"]),
+  ("mongo", &["mongodb"]),
+  ("pg", &["postgres", "postgresql"]),
+  ("pkg", &["package"]),
+  ("postgres", &["postgresql"]),
+  ("py", &["python"]),
+  ("rb", &["ruby"]),
+  ("regex", &["regular expression", "regexp"]),
+  ("repo", &["repository"]),
+  ("str", &["string"]),
+  ("ts", &["typescript"]),
+  ("tz", &["timezone"]),
+  ("vec", &["vector"]),
+];
+
+// Optional file with extra synonyms, one `term = alternative, alternative` entry per line
+pub const SYNONYMS_PATH: &str = "./synonyms.txt";
+// Boost of an expanded alternative relative to the term the user typed
+pub const SYNONYM_WEIGHT: f32 = 0.5;
+
+// Weight ( 0 - 1 ) of the freshness decay in the final score, 0 disables it
+pub const FRESHNESS_WEIGHT: f32 = 0.1;
+// Age at which a page's freshness is halved
+pub const FRESHNESS_HALF_LIFE_DAYS: f32 = 365.0;
+
+// Internal links captured while crawling, used for the link based signals ( authority ). Kept in every
+// snapshot of the index, so it is published and rolled back together with the pages it was built from
+pub const LINK_GRAPH_FILE: &str = "links.jsonl";
+// Weight of a page's authority ( 0 - 1 ) in the final score, 0 disables it
+pub const AUTHORITY_WEIGHT: f32 = 0.5;
+
+// Pages whose content fingerprints ( SimHash ) differ in at most this many of 64 bits are near-duplicates,
+// e.g. print views and mirrors. Search shows one of them with links to the others
+pub const NEAR_DUPLICATE_DISTANCE: u32 = 3;
+
+// Maximum number of results from one site before the rest of its hits are moved below other sites' results, 0 disables
+pub const MAX_RESULTS_PER_SITE: usize = 3;
+
+// Fields searched by default and their boosts. Each field's stemmed copy is searched along with it,
+// with `STEMMED_FIELD_BOOST` times its boost
+pub const FIELD_BOOSTS: [(&str, f32); 4] = [
+  ("title", 3.0),
+  ("headings", 2.0),
+  ("content", 1.0),
+  ("anchors", 2.0),
+];
+// Boost of a stemmed field relative to its exact-form field, so exact matches rank higher
+pub const STEMMED_FIELD_BOOST: f32 = 0.5;
+// Only match pages containing all the query terms ( AND ) instead of any of them ( OR )
+pub const REQUIRE_ALL_TERMS: bool = false;
+// Boost of pages containing a multi-word query's terms within `PHRASE_SLOP` positions of each other, 0 disables
+pub const PHRASE_BOOST: f32 = 2.0;
+pub const PHRASE_SLOP: u32 = 2;
+
+// Directory the API reads its config files ( synonyms, API keys ) from and writes its logs and click-through
+// priors to. The paths of those files below are relative to it
+pub const DATA_DIR: &str = ".";
+
+// Append-only log of the searches served by the API, one JSON entry per line. Feeds the search analytics
+pub const QUERY_LOG_PATH: &str = "./query_log.jsonl";
+
+// Append-only log of the results clicked from the search page, one JSON entry per line
+pub const CLICK_LOG_PATH: &str = "./clicks.jsonl";
+// Click-through priors computed from the query and click logs, used as a ranking boost
+pub const CLICK_PRIORS_PATH: &str = "./click_priors.tsv";
+// How often the click-through priors are recomputed by the API
+pub const CLICK_PRIORS_INTERVAL_SECS: u64 = 60 * 60;
+// Weight ( 0 - 1 ) of the click-through priors in the score, 0 disables them
+pub const CLICK_WEIGHT: f32 = 0.5;
+
+// Memory budget of the searcher's result cache, in bytes. Cached results are dropped on every index commit
+pub const SEARCH_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+// API keys allowed to call the admin routes ( crawling, deleting pages, reloading config ). Keys are
+// stored as SHA-256 hashes, one `name:sha256 hex:scopes` entry per line, scopes being a comma
+// separated list of `crawl`, `delete`, `config` or `*`. Keys from the `API_KEYS_ENV` environment
+// variable ( entries separated by `;` ) are added to the ones from the file
+pub const API_KEYS_PATH: &str = "./api_keys.txt";
+pub const API_KEYS_ENV: &str = "API_KEYS";
+
+// Token bucket rate limits per client ( API key, or IP address for requests without a valid key ),
+// as ( requests per minute, burst ). 0 requests per minute disables the limit. Search limits apply to
+// result clicks as well, admin limits to crawling, deleting pages and reloading config
+pub const SEARCH_RATE_LIMIT: (u32, u32) = (120, 20);
+pub const ADMIN_RATE_LIMIT: (u32, u32) = (10, 5);
+// Proxies in front of the API ( Shuttle's ) which append the address of their client to
+// `X-Forwarded-For`. Clients are identified by the address added by the outermost one, the entries
+// before it are whatever the client sent. 0 ignores the header, for an API exposed directly
+pub const RATE_LIMIT_TRUSTED_PROXIES: usize = 1;
+
+// Defaults of the standalone server ( `apps/api/src/bin/server.rs` ), overridden by its flags and
+// environment variables. 0 worker threads starts one per CPU core. The server is usually exposed
+// directly, so `X-Forwarded-For` isn't trusted unless proxies are configured
+pub const SERVER_BIND_ADDRESS: &str = "127.0.0.1";
+pub const SERVER_PORT: u16 = 3000;
+pub const SERVER_WORKER_THREADS: usize = 0;
+pub const SERVER_TRUSTED_PROXIES: usize = 0;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_site_has_an_ecosystem() {
+    for site in SITES {
+      assert!(SITE_ECOSYSTEMS.iter().any(|(ecosystem_site, _)| *ecosystem_site == site), "{}", site);
+    }
+    for (site, _) in SITE_ECOSYSTEMS {
+      assert!(SITES.contains(&site), "{}", site);
+    }
+  }
+
+  #[test]
+  fn subdomains_belong_to_their_parent_site() {
+    assert_eq!(ecosystem_for_domain("doc.rust-lang.org"), Some("rust"));
+    assert_eq!(ecosystem_for_domain("attacker.readthedocs.io"), Some("python"));
+    assert_eq!(ecosystem_for_domain("notdocs.rs"), None);
+    assert_eq!(site_for_domain("attacker.readthedocs.io"), Some("readthedocs.io"));
+    assert_eq!(site_for_domain("notreadthedocs.io"), None);
+    assert_eq!(site_for_domain("example.com"), None);
+  }
+}
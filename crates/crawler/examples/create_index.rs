@@ -2,5 +2,5 @@ use crawler::start_indexing;
 
 #[tokio::main]
 async fn main() {
-  start_indexing().await;
+  start_indexing(config::INDEX_PATH.as_ref(), &Default::default(), Default::default()).await.unwrap();
 }
\ No newline at end of file
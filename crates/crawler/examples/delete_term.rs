@@ -1,7 +1,8 @@
-use tantivy::{collector::TopDocs, query::QueryParser, schema::Field, DateTime, Document, Term};
+use tantivy::{collector::TopDocs, query::QueryParser, DateTime, Document, Term};
 
 fn main() {
-  let index = tantivy::Index::open_in_dir("../../index").unwrap();
+  let index_path = schema::SnapshotStore::new(config::INDEX_PATH).current_path().unwrap();
+  let index = tantivy::Index::open_in_dir(index_path).unwrap();
   let schema = index.schema();
   let mut writer = index.writer(50_000_000).unwrap();
   let reader = index.reader().unwrap();
@@ -28,7 +29,7 @@ fn main() {
     &TopDocs::with_limit(1)
   ).unwrap();
 
-  if results.len() == 0 {
+  if results.is_empty() {
     println!("No results found");
     return;
   }
@@ -38,7 +39,7 @@ fn main() {
   let url_id= doc.get_first(schema.get_field("url_id").unwrap())
     .and_then(|f| f.as_bytes()).unwrap();
 
-  let term = Term::from_field_bytes(schema.get_field("url_id").unwrap(), &url_id);
+  let term = Term::from_field_bytes(schema.get_field("url_id").unwrap(), url_id);
   println!("Term {:?}", term);
   writer.delete_term(term);
   writer.commit().unwrap();
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use schema::SnapshotStore;
+
+use crate::error::CrawlError;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompactStats {
+  pub segments_before: usize,
+  pub segments_after: usize,
+  // Deleted pages ( deleted or re-crawled ) whose data was dropped by the merge
+  pub deleted_docs_removed: u32,
+}
+
+// Merges all segments of the current snapshot of the index at `index_path` into one, dropping the
+// data of deleted pages, and removes the files no longer used. The crawler commits every page, so a
+// crawl leaves many small segments behind. Fails while a crawl or import builds a snapshot
+pub fn compact_index(index_path: &Path) -> Result<CompactStats, CrawlError> {
+  let snapshot = SnapshotStore::new(index_path).lock_current().map_err(CrawlError::Snapshot)?;
+  let index = schema::open_index(snapshot.path())?;
+
+  let segments = index.searchable_segment_metas()?;
+  let deleted_docs: u32 = segments.iter().map(|segment| segment.num_deleted_docs()).sum();
+
+  let mut writer = index.writer(50_000_000)?;
+  if segments.len() > 1 || deleted_docs > 0 {
+    let segment_ids: Vec<_> = segments.iter().map(|segment| segment.id()).collect();
+    writer.merge(&segment_ids).wait()?;
+  }
+  writer.garbage_collect_files().wait()?;
+  writer.wait_merging_threads()?;
+
+  Ok(CompactStats {
+    segments_before: segments.len(),
+    segments_after: index.searchable_segment_ids()?.len(),
+    deleted_docs_removed: deleted_docs,
+  })
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, NaiveDate};
+
+// Parses the date formats found in `Last-Modified` headers and meta tags into epoch milliseconds.
+// Supports RFC 3339 ( `2024-05-01T10:00:00Z` ), RFC 2822 ( `Wed, 01 May 2024 10:00:00 GMT` ) and plain `2024-05-01`
+pub fn parse_date_ms(value: &str) -> Option<i64> {
+  let value = value.trim();
+
+  DateTime::parse_from_rfc3339(value)
+    .or_else(|_| DateTime::parse_from_rfc2822(value))
+    .map(|date| date.timestamp_millis())
+    .ok()
+    .or_else(|| {
+      let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+      Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis())
+    })
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashSet, path::Path};
+
+use schema::SnapshotStore;
+use tantivy::{collector::DocSetCollector, query::QueryParser, Document, Index, Term};
+
+use crate::{doc_collector::parse_url, error::CrawlError};
+
+// Indexed pages to delete, by url ( query and fragment are ignored ) or by domain
+#[derive(Debug, Clone)]
+pub enum DocumentSelector {
+  Url(String),
+  Domain(String),
+}
+
+// Selected pages, with their stored fields. The stored url or domain must match exactly, the phrase
+// query only narrows down the candidates
+pub fn find_documents(index: &Index, selector: &DocumentSelector) -> Result<Vec<Document>, CrawlError> {
+  let schema = index.schema();
+  let field = |name: &'static str| schema.get_field(name).ok_or(CrawlError::MissingField(name));
+  let (matched_field, value) = match selector {
+    DocumentSelector::Url(url) => (field("url")?, parse_url(url)),
+    DocumentSelector::Domain(domain) => (field("domain")?, domain.to_lowercase()),
+  };
+
+  let query = QueryParser::for_index(index, vec![matched_field])
+    .parse_query(&format!("\"{}\"", value.replace('"', "")))?;
+  let reader = index.reader()?;
+  let searcher = reader.searcher();
+
+  let mut documents = Vec::new();
+  for doc_address in searcher.search(&query, &DocSetCollector)? {
+    let doc = searcher.doc(doc_address)?;
+    if doc.get_first(matched_field).and_then(|f| f.as_text()) == Some(value.as_str()) {
+      documents.push(doc);
+    }
+  }
+  Ok(documents)
+}
+
+// Deletes the selected pages from the current snapshot of the index at `index_path` and returns how
+// many were deleted. Fails while a crawl or import builds a snapshot, it would bring the pages back
+pub fn delete_documents(index_path: &Path, selector: &DocumentSelector) -> Result<usize, CrawlError> {
+  let snapshot = SnapshotStore::new(index_path).lock_current().map_err(CrawlError::Snapshot)?;
+  let index = schema::open_index(snapshot.path())?;
+
+  let url_id_field = index.schema().get_field("url_id").ok_or(CrawlError::MissingField("url_id"))?;
+
+  let mut url_ids = HashSet::new();
+  let mut num_deleted = 0;
+  for doc in find_documents(&index, selector)? {
+    if let Some(url_id) = doc.get_first(url_id_field).and_then(|f| f.as_bytes()) {
+      url_ids.insert(url_id.to_vec());
+      num_deleted += 1;
+    }
+  }
+  if url_ids.is_empty() {
+    return Ok(0);
+  }
+
+  let mut writer = index.writer(50_000_000)?;
+  for url_id in url_ids {
+    writer.delete_term(Term::from_field_bytes(url_id_field, &url_id));
+  }
+  writer.commit()?;
+
+  Ok(num_deleted)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::import_documents;
+
+  const PAGES: &str = r#"{"url": ["https://doc.rust-lang.org/std/vec/struct.Vec.html"], "title": ["Vec in std::vec"], "content": ["A contiguous growable array type."]}
+{"url": ["https://go.dev/doc/effective_go"], "title": ["Effective Go"], "content": ["Do not communicate by sharing memory."]}
+"#;
+
+  #[test]
+  fn pages_are_not_deleted_while_a_snapshot_is_built() {
+    let dir = tempfile::tempdir().unwrap();
+    let snapshots = SnapshotStore::new(dir.path());
+    let staging = snapshots.stage(false).unwrap();
+    import_documents(&schema::open_or_create_index(staging.path()).unwrap(), PAGES.as_bytes()).unwrap();
+    snapshots.publish(staging, 2).unwrap();
+
+    // A crawl publishing its copy of the current snapshot would bring the pages back
+    let crawl = snapshots.stage(true).unwrap();
+    let go = DocumentSelector::Domain("go.dev".to_string());
+    assert!(matches!(delete_documents(dir.path(), &go), Err(CrawlError::Snapshot(e)) if e.kind() == std::io::ErrorKind::WouldBlock));
+    snapshots.discard(crawl).unwrap();
+
+    assert_eq!(delete_documents(dir.path(), &go).unwrap(), 1);
+    let index = schema::open_index(snapshots.current_path().unwrap()).unwrap();
+    assert!(find_documents(&index, &go).unwrap().is_empty());
+    assert_eq!(find_documents(&index, &DocumentSelector::Domain("doc.rust-lang.org".to_string())).unwrap().len(), 1);
+  }
+}
@@ -1,12 +1,12 @@
 use std::{sync::Arc, time::{SystemTime, UNIX_EPOCH}};
 
 use anyhow::{Error, anyhow};
-use config::{NUM_PAGES_PER_SITE, RE_CRAWL_DURATION};
-use tantivy::{collector::TopDocs, query::QueryParser, DateTime, Document, Index, Term};
+use config::{ecosystem_for_domain, site_for_domain, RE_CRAWL_DURATION};
+use tantivy::{collector::TopDocs, query::QueryParser, schema::{Facet, Field}, DateTime, Document, Index, Term};
 use url::Url;
 use voyager::{Crawler, Response, Scraper};
 
-use crate::{doc_extractor::DocExtractor, extracted_content::ExtractedContent};
+use crate::{dates::parse_date_ms, doc_extractor::DocExtractor, error::CrawlError, doc_version::detect_version, extracted_content::ExtractedContent, link_graph::{Link, LinkGraph}, simhash::simhash};
 
 #[derive(Clone)]
 pub struct DocCollector {
@@ -14,6 +14,8 @@ pub struct DocCollector {
   pub schema: tantivy::schema::Schema,
   pub extractors: Arc<dashmap::DashMap<String, DocExtractor>>,
   pub counter: Arc<dashmap::DashMap<String, i32>>,
+  pub link_graph: Arc<LinkGraph>,
+  pub pages_per_site: i32,
 }
 
 fn get_epoch_ms() -> u128 {
@@ -25,44 +27,52 @@ fn get_epoch_ms() -> u128 {
 
 // Return pure url with hash fragments and query params removed
 pub fn parse_url(url: &str) -> String {
-  let url = Url::parse(url).unwrap();
-  format!("{}://{}{}", url.scheme(), url.host().unwrap(), url.path())
+  match Url::parse(url) {
+    Ok(parsed) if parsed.has_host() => format!("{}://{}{}", parsed.scheme(), parsed.host_str().unwrap_or_default(), parsed.path()),
+    _ => url.to_string(),
+  }
 }
 
 impl DocCollector {
-  pub fn should_scrape_url (&self, url: &str) -> bool {
-    let url_query_parser = QueryParser::for_index(&self.index, vec![self.schema.get_field("url").unwrap()]);
-    let url_query = url_query_parser.parse_query(format!("\"{}\"", url).as_str()).unwrap();
-    let reader = self.index.reader().unwrap();
+  fn field(&self, name: &'static str) -> Result<Field, CrawlError> {
+    self.schema.get_field(name).ok_or(CrawlError::MissingField(name))
+  }
+
+  pub fn should_scrape_url (&self, url: &str) -> Result<bool, CrawlError> {
+    let url_query_parser = QueryParser::for_index(&self.index, vec![self.field("url")?]);
+    let url_query = url_query_parser.parse_query(format!("\"{}\"", url).as_str())?;
+    let reader = self.index.reader()?;
     let results = reader.searcher().search(
       &url_query,
       &TopDocs::with_limit(1)
-    ).unwrap();
+    )?;
 
-    if results.len() == 0 {
-      return true;
+    if results.is_empty() {
+      return Ok(true);
     }
 
-    let doc = reader.searcher().doc(results[0].1).unwrap();
-    let scraped_at_ms = doc.get_first(self.schema.get_field("scraped_at").unwrap())
-      .and_then(|f| f.as_date()).unwrap().into_timestamp_millis();
+    let doc = reader.searcher().doc(results[0].1)?;
+    // Pages without a crawl date are crawled again
+    let scraped_at_ms = doc.get_first(self.field("scraped_at")?)
+      .and_then(|f| f.as_date()).map(|d| d.into_timestamp_millis()).unwrap_or_default();
     let current_ts_ms = get_epoch_ms() as i64;
 
     if current_ts_ms - scraped_at_ms > RE_CRAWL_DURATION {
       // if site is already crawled, we need to delete the older data as a side effect
-      let url_id= doc.get_first(self.schema.get_field("url_id").unwrap())
-        .and_then(|f| f.as_bytes()).unwrap();
-      let term = Term::from_field_bytes(self.schema.get_field("url_id").unwrap(), &url_id);
-      let mut writer = self.index.writer(50_000_000).unwrap();
-
-      // Look for a better mechanism to solve this as deleting is expensive
-      writer.delete_term(term);
-      writer.commit().unwrap();
-      drop(writer);
-      return true;
+      let url_id_field = self.field("url_id")?;
+      if let Some(url_id) = doc.get_first(url_id_field).and_then(|f| f.as_bytes()) {
+        let term = Term::from_field_bytes(url_id_field, url_id);
+        let mut writer = self.index.writer(50_000_000)?;
+
+        // Look for a better mechanism to solve this as deleting is expensive
+        writer.delete_term(term);
+        writer.commit()?;
+        drop(writer);
+      }
+      return Ok(true);
     }
 
-    return false;
+    Ok(false)
   }
 }
 
@@ -76,11 +86,16 @@ impl Scraper for DocCollector {
 
     let domain = url.domain().ok_or_else(|| anyhow!("No domain found"))?;
 
-    let extractor = self.extractors.entry(domain.to_string())
-    .or_insert_with(|| DocExtractor::new(domain).unwrap())
-    .clone();
+    let extractor = match self.extractors.get(domain) {
+      Some(extractor) => extractor.clone(),
+      None => {
+        let extractor = DocExtractor::new(domain)?;
+        self.extractors.insert(domain.to_string(), extractor.clone());
+        extractor
+      },
+    };
 
-    if !self.should_scrape_url(parse_url(url.as_str()).as_str()) {
+    if !self.should_scrape_url(parse_url(url.as_str()).as_str())? {
       return Ok(None);
     }
 
@@ -89,26 +104,73 @@ impl Scraper for DocCollector {
       let mut doc = Document::default();
       println!("title: {} url: {}", content.title, url.as_str());
 
-      doc.add_text(self.schema.get_field("title").unwrap(), &content.title);
-      doc.add_text(self.schema.get_field("content").unwrap(), &content.content.join("\n"));
-      doc.add_text(self.schema.get_field("url").unwrap(), parse_url(url.as_str()).as_str());
+      doc.add_text(self.field("title")?, &content.title);
+      let text = content.content.join("\n");
+      doc.add_text(self.field("content")?, &text);
+      if let Some(fingerprint) = simhash(&text) {
+        doc.add_u64(self.field("simhash")?, fingerprint);
+      }
+      doc.add_text(self.field("url")?, parse_url(url.as_str()).as_str());
 
-      doc.add_bytes(self.schema.get_field("url_id").unwrap(), url.as_str());
-      doc.add_text(self.schema.get_field("domain").unwrap(), domain);
-      doc.add_text(self.schema.get_field("headings").unwrap(), 
+      doc.add_bytes(self.field("url_id")?, url.as_str());
+      doc.add_text(self.field("domain")?, domain);
+      doc.add_text(self.field("headings")?, 
       content.headings.join("\n"));
-      doc.add_date(self.schema.get_field("scraped_at").unwrap(), DateTime::from_timestamp_millis(get_epoch_ms() as i64));
+      let scraped_at = get_epoch_ms() as i64;
+      doc.add_date(self.field("scraped_at")?, DateTime::from_timestamp_millis(scraped_at));
+
+      // Prefer the date from the page's meta tags over the header, which is often the deploy time
+      let last_modified = content.last_modified
+        .or_else(|| {
+          response.response_headers.get("last-modified")
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_date_ms)
+        })
+        .filter(|last_modified| *last_modified <= scraped_at)
+        .unwrap_or(scraped_at);
+      doc.add_date(self.field("last_modified")?, DateTime::from_timestamp_millis(last_modified));
+      if let Some(ecosystem) = ecosystem_for_domain(domain) {
+        doc.add_facet(self.field("lang")?, Facet::from_path(vec![ecosystem]));
+      }
+      doc.add_facet(self.field("site")?, Facet::from_path(vec![site_for_domain(domain).unwrap_or(domain)]));
+      if let Some(doc_version) = detect_version(&url) {
+        doc.add_text(self.field("product")?, &doc_version.product);
+        doc.add_text(self.field("version")?, &doc_version.version);
+        doc.add_text(self.field("page_key")?, &doc_version.page_key);
+      }
+      if let Some(language) = &content.language {
+        doc.add_text(self.field("page_lang")?, language);
+      }
+      schema::add_derived_fields(&self.index, &mut doc);
 
-      let mut writer = self.index.writer(50_000_000).unwrap();
+      let mut writer = self.index.writer(50_000_000)?;
       writer.add_document(doc)?;
-      writer.commit().unwrap();
+      writer.commit()?;
       drop(writer);
 
       let links = html.select(&voyager::scraper::Selector::parse("a").unwrap())
-        .map(|e| e.value().attr("href").unwrap_or_default())
+        .map(|e| (e.value().attr("href").unwrap_or_default(), e.text().collect::<Vec<_>>().join(" ")))
         .collect::<Vec<_>>();
 
-      for link in links {
+      // Keep the internal links of the page for the link graph
+      let source_url = parse_url(url.as_str());
+      let mut page_links = Vec::new();
+      for (link, anchor) in &links {
+        let Ok(target_url) = url.join(link) else {
+          continue;
+        };
+        if !matches!(target_url.scheme(), "http" | "https") || target_url.domain() != Some(domain) {
+          continue;
+        }
+
+        let target = parse_url(target_url.as_str());
+        if target != source_url {
+          page_links.push(Link { target, anchor: anchor.split_whitespace().collect::<Vec<_>>().join(" ") });
+        }
+      }
+      self.link_graph.record(&source_url, page_links)?;
+
+      for (link, _) in links {
         let mut scrape_url = None;
         if let Ok(url) = Url::parse(link) {
           scrape_url = Some(url);
@@ -124,15 +186,14 @@ impl Scraper for DocCollector {
           // Before visiting the link, check if it is already scraped
           let url = scrape_url.clone();
           let domain = url.domain().ok_or_else(|| anyhow!("No domain found"))?;
-          let counter = self.counter.entry(domain.to_string())
-            .or_insert_with(|| 0)
-            .clone();
+          let counter = *self.counter.entry(domain.to_string())
+            .or_insert_with(|| 0);
 
-          if counter > NUM_PAGES_PER_SITE {
+          if counter > self.pages_per_site {
             continue;
           }
 
-          if !self.should_scrape_url(parse_url(url.as_str()).as_str()) {
+          if !self.should_scrape_url(parse_url(url.as_str()).as_str())? {
             continue;
           }
 
@@ -144,6 +205,6 @@ impl Scraper for DocCollector {
       return Ok(Some(content));
     }
 
-    return Ok(None);
+    Ok(None)
   }
 }
\ No newline at end of file
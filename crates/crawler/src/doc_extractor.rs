@@ -1,13 +1,25 @@
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use voyager::scraper::Html;
-use spider::{configuration::RedirectPolicy, page::Page, website::Website};
-use url::Url;
+use spider::{configuration::RedirectPolicy, website::Website};
 
-use crate::extracted_content::ExtractedContent;
+use crate::{dates::parse_date_ms, extracted_content::ExtractedContent};
+
+// Meta tags which carry the last modification date of a page, as ( selector, attribute with the date )
+const LAST_MODIFIED_TAGS: [(&str, &str); 7] = [
+  (r#"meta[property="article:modified_time"]"#, "content"),
+  (r#"meta[property="og:updated_time"]"#, "content"),
+  (r#"meta[name="last-modified"]"#, "content"),
+  (r#"meta[http-equiv="last-modified"]"#, "content"),
+  (r#"meta[name="dcterms.modified"]"#, "content"),
+  (r#"meta[itemprop="dateModified"]"#, "content"),
+  (r#"time[itemprop="dateModified"]"#, "datetime"),
+];
 
 
 #[derive(Debug, Clone)]
 pub struct DocExtractor {
+  // Not used by `extract_content` yet, kept for the spider based extraction
+  #[allow(dead_code)]
   website: Website
 }
 
@@ -16,7 +28,8 @@ impl DocExtractor {
     let website = Website::new(domain)
       .with_respect_robots_txt(true)
       .with_redirect_policy(RedirectPolicy::Strict)
-      .build().unwrap();
+      .build()
+      .map_err(|_| anyhow!("Invalid website: {}", domain))?;
 
     Ok(Self { website })
   }
@@ -35,6 +48,15 @@ impl DocExtractor {
       title: title.to_string(),
       content: html.select(&voyager::scraper::Selector::parse("body").unwrap()).map(|e| e.text().collect()).collect(),
       headings: html.select(&voyager::scraper::Selector::parse("h1, h2, h3").unwrap()).map(|e| e.text().collect()).collect(),
+      language: html.select(&voyager::scraper::Selector::parse("html").unwrap())
+        .filter_map(|e| e.value().attr("lang"))
+        .map(|lang| lang.trim().to_lowercase())
+        .find(|lang| !lang.is_empty()),
+      last_modified: LAST_MODIFIED_TAGS.iter().find_map(|(selector, attr)| {
+        html.select(&voyager::scraper::Selector::parse(selector).unwrap())
+          .filter_map(|e| e.value().attr(attr))
+          .find_map(parse_date_ms)
+      }),
     })
   }
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+use config::VERSION_PATTERNS;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocVersion {
+  pub product: String,
+  pub version: String,
+  // Url of the page with the version segment removed. Identical for every version of the same page
+  pub page_key: String,
+}
+
+static PATTERNS: Lazy<Vec<(&str, &str, Regex)>> = Lazy::new(|| {
+  VERSION_PATTERNS.iter()
+    .map(|(site, product, pattern)| (*site, *product, Regex::new(pattern).unwrap()))
+    .collect()
+});
+
+// Detects the documentation version of a page from its url using the per-site `VERSION_PATTERNS`
+pub fn detect_version(url: &Url) -> Option<DocVersion> {
+  let domain = url.domain()?;
+  let path = url.path();
+
+  let (_, product, pattern) = PATTERNS.iter()
+    .find(|(site, _, _)| domain == *site || domain.ends_with(&format!(".{}", site)))?;
+  let captures = pattern.captures(path)?;
+  let version = captures.name("version")?;
+
+  let product = match captures.name("product") {
+    Some(m) => m.as_str(),
+    None if product.is_empty() => domain,
+    None => product,
+  };
+
+  let page_key = format!(
+    "{}{}*{}",
+    domain,
+    &path[..version.start()],
+    &path[version.end()..]
+  );
+
+  Some(DocVersion {
+    product: product.to_string(),
+    version: version.as_str().to_string(),
+    page_key,
+  })
+}
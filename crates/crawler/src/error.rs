@@ -0,0 +1,24 @@
+use std::io;
+
+use tantivy::{query::QueryParserError, TantivyError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CrawlError {
+  #[error("Index error: {0}")]
+  Index(#[from] TantivyError),
+  #[error("Invalid url query: {0}")]
+  Query(#[from] QueryParserError),
+  // The index was created with an older schema, it has to be rebuilt
+  #[error("Field `{0}` is missing from the index schema")]
+  MissingField(&'static str),
+  #[error("Link graph error: {0}")]
+  LinkGraph(#[from] io::Error),
+  // Reading or writing exported documents
+  #[error("Failed to read or write documents: {0}")]
+  Documents(#[source] io::Error),
+  // Creating, publishing or discarding a snapshot of the index
+  #[error("Index snapshot error: {0}")]
+  Snapshot(#[source] io::Error),
+  #[error("Invalid document on line {line}: {message}")]
+  InvalidDocument { line: usize, message: String },
+}
@@ -0,0 +1,223 @@
+use std::{collections::BTreeMap, io::{BufRead, Write}, time::{SystemTime, UNIX_EPOCH}};
+
+use config::{ecosystem_for_domain, site_for_domain};
+use serde_json::{Map, Value};
+use tantivy::{schema::{Facet, Field, Schema}, DateTime, Document, Index, Term};
+use url::Url;
+
+use crate::{doc_version::detect_version, error::CrawlError, simhash::simhash};
+
+// Writes every stored page as one JSON object per line, mapping field names to their values. Pages
+// are sorted by url and fields by name, so exports of the same content can be diffed. The fields
+// which aren't stored ( the stemmed copies ) are derived again on import
+pub fn export_documents(index: &Index, mut output: impl Write) -> Result<usize, CrawlError> {
+  let schema = index.schema();
+  let url_field = schema.get_field("url").ok_or(CrawlError::MissingField("url"))?;
+  let reader = index.reader()?;
+  let searcher = reader.searcher();
+
+  let mut lines = Vec::new();
+  for segment_reader in searcher.segment_readers() {
+    let store_reader = segment_reader.get_store_reader(1).map_err(CrawlError::Documents)?;
+    for doc in store_reader.iter(segment_reader.alive_bitset()) {
+      let doc = doc?;
+      let url = doc.get_first(url_field).and_then(|value| value.as_text()).unwrap_or_default().to_string();
+      lines.push((url, schema.to_json(&doc)));
+    }
+  }
+  lines.sort();
+
+  for (_, line) in &lines {
+    writeln!(output, "{}", line).map_err(CrawlError::Documents)?;
+  }
+  output.flush().map_err(CrawlError::Documents)?;
+
+  Ok(lines.len())
+}
+
+// Outcome of an import. Both maps count pages per field name
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+  pub imported: usize,
+  // Fields of the export the index's schema doesn't have ( removed since the export )
+  pub dropped_fields: BTreeMap<String, usize>,
+  // Fields missing from the export which were derived from the page's url and content, like the
+  // crawler does ( added since the export, or left out of a hand-written seed file )
+  pub derived_fields: BTreeMap<String, usize>,
+}
+
+// Adds the pages of an export to the index with its current schema, replacing the indexed pages with
+// the same url. Every page needs a `url`, the fields the crawler derives from it are filled in when
+// missing. Link signals ( `authority`, `anchors` ) are only computed by the next crawl
+pub fn import_documents(index: &Index, input: impl BufRead) -> Result<ImportReport, CrawlError> {
+  let schema = index.schema();
+  let url_id_field = schema.get_field("url_id").ok_or(CrawlError::MissingField("url_id"))?;
+  let mut writer = index.writer(50_000_000)?;
+
+  let mut report = ImportReport::default();
+  for (line_number, line) in input.lines().enumerate() {
+    let line = line.map_err(CrawlError::Documents)?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let invalid = |message: String| CrawlError::InvalidDocument { line: line_number + 1, message };
+
+    let mut fields: Map<String, Value> = serde_json::from_str(&line).map_err(|e| invalid(e.to_string()))?;
+    fields.retain(|name, _| {
+      let known = schema.get_field(name).is_some();
+      if !known {
+        *report.dropped_fields.entry(name.clone()).or_default() += 1;
+      }
+      known
+    });
+
+    let mut doc = schema.json_object_to_doc(fields).map_err(|e| invalid(e.to_string()))?;
+    for field_name in derive_missing_fields(&schema, &mut doc).map_err(invalid)? {
+      *report.derived_fields.entry(field_name.to_string()).or_default() += 1;
+    }
+
+    if let Some(url_id) = doc.get_first(url_id_field).and_then(|value| value.as_bytes()) {
+      writer.delete_term(Term::from_field_bytes(url_id_field, url_id));
+    }
+    schema::add_derived_fields(index, &mut doc);
+    writer.add_document(doc)?;
+    report.imported += 1;
+  }
+  writer.commit()?;
+
+  Ok(report)
+}
+
+// Fills the stored fields the crawler derives from the url and content, returning the names of the
+// fields it added
+fn derive_missing_fields(schema: &Schema, doc: &mut Document) -> Result<Vec<&'static str>, String> {
+  let field = |name: &'static str| schema.get_field(name).map(|field| (name, field));
+  let url = field("url")
+    .and_then(|(_, url_field)| doc.get_first(url_field))
+    .and_then(|value| value.as_text())
+    .ok_or_else(|| "Missing `url`".to_string())?;
+  let url = Url::parse(url).map_err(|e| format!("Invalid url `{}`: {}", url, e))?;
+  let domain = url.domain().ok_or_else(|| format!("No domain in `{}`", url))?.to_string();
+  let content = field("content")
+    .and_then(|(_, content_field)| doc.get_first(content_field))
+    .and_then(|value| value.as_text())
+    .unwrap_or_default()
+    .to_string();
+
+  let mut derived = Vec::new();
+  let mut missing = |name: &'static str, doc: &Document| -> Option<Field> {
+    let (name, field) = field(name)?;
+    if doc.get_first(field).is_some() {
+      return None;
+    }
+    derived.push(name);
+    Some(field)
+  };
+
+  if let Some(field) = missing("url_id", doc) {
+    doc.add_bytes(field, url.as_str());
+  }
+  if let Some(field) = missing("domain", doc) {
+    doc.add_text(field, &domain);
+  }
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as i64).unwrap_or_default();
+  let scraped_at = match field("scraped_at").and_then(|(_, scraped_at_field)| doc.get_first(scraped_at_field)) {
+    Some(value) => value.as_date().map(|date| date.into_timestamp_millis()).unwrap_or(now),
+    None => now,
+  };
+  if let Some(field) = missing("scraped_at", doc) {
+    doc.add_date(field, DateTime::from_timestamp_millis(scraped_at));
+  }
+  if let Some(field) = missing("last_modified", doc) {
+    doc.add_date(field, DateTime::from_timestamp_millis(scraped_at));
+  }
+  if let Some(ecosystem) = ecosystem_for_domain(&domain) {
+    if let Some(field) = missing("lang", doc) {
+      doc.add_facet(field, Facet::from_path(vec![ecosystem]));
+    }
+  }
+  if let Some(field) = missing("site", doc) {
+    doc.add_facet(field, Facet::from_path(vec![site_for_domain(&domain).unwrap_or(&domain)]));
+  }
+  if let Some(doc_version) = detect_version(&url) {
+    for (name, value) in [("product", &doc_version.product), ("version", &doc_version.version), ("page_key", &doc_version.page_key)] {
+      if let Some(field) = missing(name, doc) {
+        doc.add_text(field, value);
+      }
+    }
+  }
+  if let Some(fingerprint) = simhash(&content) {
+    if let Some(field) = missing("simhash", doc) {
+      doc.add_u64(field, fingerprint);
+    }
+  }
+
+  Ok(derived)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SEED: &str = r#"{"url": ["https://doc.rust-lang.org/1.70.0/std/vec/struct.Vec.html"], "title": ["Vec in std::vec"], "content": ["A contiguous growable array type, written as Vec<T>."], "rank": [3]}
+{"url": ["https://go.dev/doc/effective_go"], "title": ["Effective Go"], "content": ["Do not communicate by sharing memory."], "scraped_at": ["2024-01-01T00:00:00Z"]}
+"#;
+
+  fn export(index: &Index) -> String {
+    let mut output = Vec::new();
+    export_documents(index, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+  }
+
+  #[test]
+  fn exports_import_into_a_fresh_index_unchanged() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = schema::open_or_create_index(dir.path().join("seeded")).unwrap();
+    let report = import_documents(&index, SEED.as_bytes()).unwrap();
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.dropped_fields, BTreeMap::from([("rank".to_string(), 1)]));
+    assert_eq!(report.derived_fields["url_id"], 2);
+    assert_eq!(report.derived_fields["scraped_at"], 1);
+    assert_eq!(report.derived_fields["version"], 1);
+
+    let exported = export(&index);
+    let urls: Vec<String> = exported.lines()
+      .map(|line| serde_json::from_str::<Value>(line).unwrap()["url"][0].as_str().unwrap().to_string())
+      .collect();
+    assert_eq!(urls, ["https://doc.rust-lang.org/1.70.0/std/vec/struct.Vec.html", "https://go.dev/doc/effective_go"]);
+
+    let restored = schema::open_or_create_index(dir.path().join("restored")).unwrap();
+    let report = import_documents(&restored, exported.as_bytes()).unwrap();
+    assert_eq!((report.imported, report.derived_fields.len(), report.dropped_fields.len()), (2, 0, 0));
+    assert_eq!(export(&restored), exported);
+  }
+
+  #[test]
+  fn pages_with_the_same_url_replace_each_other() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = schema::open_or_create_index(dir.path()).unwrap();
+    import_documents(&index, SEED.as_bytes()).unwrap();
+
+    let update = r#"{"url": ["https://go.dev/doc/effective_go"], "title": ["Effective Go ( draft )"]}
+{"url": ["https://go.dev/doc/effective_go"], "title": ["Effective Go ( updated )"]}"#;
+    assert_eq!(import_documents(&index, update.as_bytes()).unwrap().imported, 2);
+
+    let exported = export(&index);
+    assert_eq!(exported.lines().count(), 2);
+    assert!(exported.contains("Effective Go ( updated )"));
+    assert!(!exported.contains("Effective Go ( draft )"));
+  }
+
+  #[test]
+  fn invalid_pages_abort_the_import_with_their_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = schema::open_or_create_index(dir.path()).unwrap();
+
+    let input = format!("{}\n{{\"title\": [\"No url\"]}}\n", SEED);
+    match import_documents(&index, input.as_bytes()) {
+      Err(CrawlError::InvalidDocument { line, message }) => assert_eq!((line, message.as_str()), (4, "Missing `url`")),
+      result => panic!("Expected an invalid document, got {:?}", result.map(|report| report.imported)),
+    }
+    assert_eq!(export(&index), "");
+  }
+}
@@ -3,4 +3,8 @@ pub struct ExtractedContent {
     pub title: String,
     pub content: Vec<String>,
     pub headings: Vec<String>,
+    // Primary language of the page from `<html lang>`, e.g. `en`
+    pub language: Option<String>,
+    // Last modification date from the page's meta tags, in epoch milliseconds
+    pub last_modified: Option<i64>,
 }
\ No newline at end of file
@@ -1,75 +1,142 @@
+mod compact;
+mod dates;
+mod delete_documents;
 mod doc_extractor;
+mod doc_version;
 mod extracted_content;
 mod doc_collector;
+mod error;
+mod export;
+mod link_graph;
+mod link_signals;
+mod simhash;
 
-use std::{sync::Arc, time::Duration};
+use std::{path::Path, sync::Arc, time::Duration};
 
-use config::SITES;
+use config::{INDEX_SNAPSHOTS_KEPT, LINK_GRAPH_FILE, MAX_CONCURRENT_REQUESTS, NUM_PAGES_PER_SITE, SITES};
 use doc_collector::DocCollector;
-use tantivy::{schema::INDEXED, DateOptions};
+use link_graph::LinkGraph;
+use schema::SnapshotStore;
+pub use compact::{compact_index, CompactStats};
+pub use delete_documents::{delete_documents, find_documents, DocumentSelector};
+pub use error::CrawlError;
+pub use export::{export_documents, import_documents, ImportReport};
+use tokio_util::sync::CancellationToken;
 use voyager::{Collector, CrawlerConfig, RequestDelay};
 use futures::StreamExt;
 
-pub async fn start_indexing() {
+// What a crawl visits, defaults to every site in `config::SITES` with the limits from `config`
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+  // Domains from `config::SITES`, crawled one after the other
+  pub sites: Vec<String>,
+  pub pages_per_site: i32,
+  pub max_concurrent_requests: usize,
+}
+
+impl Default for CrawlOptions {
+  fn default() -> Self {
+    Self {
+      sites: SITES.iter().map(|site| site.to_string()).collect(),
+      pages_per_site: NUM_PAGES_PER_SITE,
+      max_concurrent_requests: MAX_CONCURRENT_REQUESTS,
+    }
+  }
+}
+
+// Crawls the sites of `options` into a new snapshot of the index at `index_path`, starting from the
+// pages of the current snapshot. Cancelling `cancel` stops visiting new pages, the pages indexed so far
+// are kept and their link signals updated. The snapshot is only published once the crawl ended, a
+// failed crawl leaves the served index untouched
+pub async fn start_indexing(index_path: &Path, options: &CrawlOptions, cancel: CancellationToken) -> Result<(), CrawlError> {
+    let snapshots = SnapshotStore::new(index_path);
+    let staging = snapshots.stage(true).map_err(CrawlError::Snapshot)?;
+
+    match crawl(staging.path(), options, cancel).await {
+      Ok(()) => {
+        let id = snapshots.publish(staging, INDEX_SNAPSHOTS_KEPT).map_err(CrawlError::Snapshot)?;
+        println!("Published index snapshot {}", id);
+        Ok(())
+      },
+      Err(e) => {
+        let staging_path = staging.path().to_path_buf();
+        if let Err(discard_error) = snapshots.discard(staging) {
+          println!("Failed to remove {}: {}", staging_path.display(), discard_error);
+        }
+        Err(e)
+      },
+    }
+}
+
+async fn crawl(index_path: &Path, options: &CrawlOptions, cancel: CancellationToken) -> Result<(), CrawlError> {
     println!("Starting indexing...");
-    let mut schema_builder = tantivy::schema::Schema::builder();
-    schema_builder.add_text_field("title", tantivy::schema::TEXT | tantivy::schema::STORED);
-    schema_builder.add_text_field("content", tantivy::schema::TEXT | tantivy::schema::STORED);
-    schema_builder.add_text_field("url", tantivy::schema::STORED | tantivy::schema::TEXT);
-    schema_builder.add_bytes_field("url_id", tantivy::schema::STORED | tantivy::schema::INDEXED);
-
-    // TODO: Use a facet for this field
-    schema_builder.add_text_field("domain", tantivy::schema::STORED | tantivy::schema::TEXT);
-    schema_builder.add_text_field("headings", tantivy::schema::TEXT | tantivy::schema::STORED);
-    
-    let date_field_opts = DateOptions::from(INDEXED).set_stored().set_precision(tantivy::schema::DatePrecision::Milliseconds);
-    schema_builder.add_date_field("scraped_at", date_field_opts);
-    let schema = schema_builder.build();
-
-    let index_dir = tantivy::directory::MmapDirectory::open("./index").unwrap();
-    let index = if tantivy::Index::exists(&index_dir).unwrap() {
-      tantivy::Index::open(index_dir).unwrap()
-    } else {
-      tantivy::Index::create_in_dir("./index", schema.clone()).unwrap()
-    };
+    let index = schema::open_or_create_index(index_path)?;
+    let schema = index.schema();
+    let link_graph = Arc::new(LinkGraph::open(index_path.join(LINK_GRAPH_FILE))?);
 
     let config = CrawlerConfig::default()
       .allow_domains_with_delay(
-        SITES.iter().map(|site| {
+        options.sites.iter().map(|site| {
           (site.to_string(), RequestDelay::Random { min: Duration::from_millis(500), max: Duration::from_millis(2000) })
         })
       )
       .respect_robots_txt()
-      .max_concurrent_requests(10);
+      .max_concurrent_requests(options.max_concurrent_requests);
 
     let doc_collector = DocCollector {
       index: Arc::new(index),
       schema,
       extractors: Arc::new(dashmap::DashMap::new()),
       counter: Arc::new(dashmap::DashMap::new()),
+      link_graph: link_graph.clone(),
+      pages_per_site: options.pages_per_site,
     };
 
     let mut collector = Collector::new(doc_collector.clone(), config);
 
-    for curr_chunk_sites in SITES.chunks(1) {
+    for curr_chunk_sites in options.sites.chunks(1) {
+      if cancel.is_cancelled() {
+        break;
+      }
+
       println!("Indexing sites: {:?}", curr_chunk_sites);
       for site in curr_chunk_sites {
         println!("Site: {}", site);
         collector.crawler_mut().visit_with_state(
-          &format!("https://{}", site),
+          format!("https://{}", site),
           ()
         );
       }
       println!("Visiting sites...");
 
-      while let Some(output) = collector.next().await {
-        if let Ok(post) = output {
-          // println!("{:?}", post.headings);
+      // Pages still being fetched are dropped when the crawl is cancelled
+      loop {
+        tokio::select! {
+          output = collector.next() => {
+            let Some(output) = output else {
+              break;
+            };
+            if let Ok(_post) = output {
+              // println!("{:?}", _post.headings);
+            }
+          },
+          _ = cancel.cancelled() => break,
         }
-      };
+      }
 
       println!("Completed indexing sites: {:?}", curr_chunk_sites);
     }
 
+    if cancel.is_cancelled() {
+      println!("Indexing cancelled");
+    }
+
+    println!("Updating link signals...");
+    link_graph.compact()?;
+    let graph = link_graph.load()?;
+    let num_updated = link_signals::update_link_signals(&doc_collector.index, &graph)?;
+    println!("Updated link signals of {} pages", num_updated);
+
     println!("Indexing complete!");
+    Ok(())
 }
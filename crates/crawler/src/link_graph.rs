@@ -0,0 +1,79 @@
+use std::{collections::HashMap, fs::{self, File, OpenOptions}, io::{self, BufRead, BufReader, Write}, path::{Path, PathBuf}, sync::{Mutex, MutexGuard, PoisonError}};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+  pub target: String,
+  pub anchor: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PageLinks {
+  source: String,
+  links: Vec<Link>,
+}
+
+// Internal links between crawled pages, persisted as JSON lines of ( source page, outgoing links ).
+// A page which is crawled again replaces its previous links
+pub struct LinkGraph {
+  path: PathBuf,
+  file: Mutex<File>,
+}
+
+fn open_for_append(path: &Path) -> io::Result<File> {
+  OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl LinkGraph {
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    let path = path.as_ref().to_path_buf();
+    let file = open_for_append(&path)?;
+
+    Ok(Self { path, file: Mutex::new(file) })
+  }
+
+  // Lines are written whole, a panic while holding the lock can't leave the file half updated
+  fn lock_file(&self) -> MutexGuard<'_, File> {
+    self.file.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  pub fn record(&self, source: &str, links: Vec<Link>) -> io::Result<()> {
+    let line = serde_json::to_string(&PageLinks { source: source.to_string(), links })?;
+    let mut file = self.lock_file();
+    writeln!(file, "{}", line)
+  }
+
+  // Latest outgoing links of every recorded page, keyed by the source url
+  pub fn load(&self) -> io::Result<HashMap<String, Vec<Link>>> {
+    let _file = self.lock_file();
+    let reader = BufReader::new(File::open(&self.path)?);
+
+    let mut graph = HashMap::new();
+    for line in reader.lines() {
+      // Skip lines which were only partially written, e.g. when a crawl was killed
+      if let Ok(page) = serde_json::from_str::<PageLinks>(&line?) {
+        graph.insert(page.source, page.links);
+      }
+    }
+
+    Ok(graph)
+  }
+
+  // Rewrites the file keeping only the latest links of every page
+  pub fn compact(&self) -> io::Result<()> {
+    let graph = self.load()?;
+    let mut file = self.lock_file();
+
+    let tmp_path = self.path.with_extension("jsonl.tmp");
+    let mut tmp_file = File::create(&tmp_path)?;
+    for (source, links) in graph {
+      writeln!(tmp_file, "{}", serde_json::to_string(&PageLinks { source, links })?)?;
+    }
+    tmp_file.sync_all()?;
+    fs::rename(&tmp_path, &self.path)?;
+
+    *file = open_for_append(&self.path)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet};
+
+use tantivy::{collector::DocSetCollector, query::AllQuery, schema::Value, Document, Index, Term};
+use url::Url;
+
+use crate::{error::CrawlError, link_graph::Link};
+
+const DAMPING: f64 = 0.85;
+const ITERATIONS: usize = 20;
+// Scores closer than this are considered unchanged, so the page isn't re-indexed
+const EPSILON: f64 = 1e-6;
+// Popular pages are linked with the same few texts, the rest only adds noise
+const MAX_ANCHORS_PER_PAGE: usize = 50;
+
+fn site_of(url: &str) -> Option<String> {
+  Url::parse(url).ok()?.host_str().map(|host| host.to_string())
+}
+
+// PageRank of a single site's pages, scaled so that the top page scores 1
+fn site_page_rank(pages: &[&String], graph: &HashMap<String, Vec<Link>>) -> HashMap<String, f64> {
+  let ids: HashMap<&String, usize> = pages.iter().enumerate().map(|(id, page)| (*page, id)).collect();
+  let num_pages = pages.len();
+
+  // Duplicate links between two pages count once
+  let out_links: Vec<Vec<usize>> = pages.iter()
+    .map(|page| {
+      let targets: HashSet<usize> = graph.get(*page).into_iter().flatten()
+        .filter_map(|link| ids.get(&link.target).copied())
+        .collect();
+      targets.into_iter().collect()
+    })
+    .collect();
+
+  let mut ranks = vec![1.0 / num_pages as f64; num_pages];
+  for _ in 0..ITERATIONS {
+    // Pages without outgoing links spread their rank over every page of the site
+    let dangling: f64 = out_links.iter().zip(&ranks)
+      .filter(|(targets, _)| targets.is_empty())
+      .map(|(_, rank)| rank)
+      .sum();
+
+    let mut next = vec![(1.0 - DAMPING + DAMPING * dangling) / num_pages as f64; num_pages];
+    for (source, targets) in out_links.iter().enumerate() {
+      for target in targets {
+        next[*target] += DAMPING * ranks[source] / targets.len() as f64;
+      }
+    }
+    ranks = next;
+  }
+
+  let max_rank = ranks.iter().cloned().fold(0.0, f64::max);
+  pages.iter()
+    .zip(ranks)
+    .map(|(page, rank)| (page.to_string(), if max_rank > 0.0 { rank / max_rank } else { 0.0 }))
+    .collect()
+}
+
+// Authority ( 0 - 1 ) of every page in the link graph. PageRank is computed per site over the
+// internal links, so large sites don't drown small ones
+pub fn compute_authority(graph: &HashMap<String, Vec<Link>>) -> HashMap<String, f64> {
+  let mut pages_per_site: HashMap<String, HashSet<&String>> = HashMap::new();
+  for (source, links) in graph {
+    for page in std::iter::once(source).chain(links.iter().map(|link| &link.target)) {
+      if let Some(site) = site_of(page) {
+        pages_per_site.entry(site).or_default().insert(page);
+      }
+    }
+  }
+
+  pages_per_site.values()
+    .flat_map(|pages| {
+      let mut pages: Vec<&String> = pages.iter().copied().collect();
+      pages.sort();
+      site_page_rank(&pages, graph)
+    })
+    .collect()
+}
+
+// Distinct anchor texts of the links pointing to every page, in the order they were first seen
+pub fn collect_anchors(graph: &HashMap<String, Vec<Link>>) -> HashMap<String, Vec<String>> {
+  let mut sources: Vec<&String> = graph.keys().collect();
+  sources.sort();
+
+  let mut anchors: HashMap<String, Vec<String>> = HashMap::new();
+  for source in sources {
+    for link in &graph[source] {
+      let page_anchors = anchors.entry(link.target.clone()).or_default();
+      if link.anchor.is_empty() || page_anchors.len() >= MAX_ANCHORS_PER_PAGE {
+        continue;
+      }
+      if !page_anchors.iter().any(|anchor| anchor.eq_ignore_ascii_case(&link.anchor)) {
+        page_anchors.push(link.anchor.clone());
+      }
+    }
+  }
+
+  anchors
+}
+
+// Stores the link based signals of every indexed page ( `authority` and `anchors` ) and re-indexes
+// the pages whose signals changed, including pages crawled before their inbound links were seen.
+// Returns the number of re-indexed pages
+pub fn update_link_signals(index: &Index, graph: &HashMap<String, Vec<Link>>) -> Result<usize, CrawlError> {
+  let schema = index.schema();
+  let field = |name: &'static str| schema.get_field(name).ok_or(CrawlError::MissingField(name));
+  let url_field = field("url")?;
+  let url_id_field = field("url_id")?;
+  let authority_field = field("authority")?;
+  let anchors_field = field("anchors")?;
+
+  let authority = compute_authority(graph);
+  let anchors = collect_anchors(graph);
+
+  let reader = index.reader()?;
+  let searcher = reader.searcher();
+  let doc_addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+
+  // Documents are deleted by `url_id`, so every document sharing one is re-added together
+  let mut changed: HashMap<Vec<u8>, Vec<Document>> = HashMap::new();
+  let mut unchanged: HashMap<Vec<u8>, Vec<Document>> = HashMap::new();
+  for doc_address in doc_addresses {
+    let doc = searcher.doc(doc_address)?;
+    let Some(url_id) = doc.get_first(url_id_field).and_then(|f| f.as_bytes()).map(|id| id.to_vec()) else {
+      continue;
+    };
+
+    let url = doc.get_first(url_field).and_then(|f| f.as_text()).unwrap_or_default();
+    let new_authority = authority.get(url).copied().unwrap_or_default();
+    let old_authority = doc.get_first(authority_field).and_then(|f| f.as_f64()).unwrap_or_default();
+    let new_anchors = anchors.get(url).map(|anchors| anchors.join("\n")).unwrap_or_default();
+    let old_anchors = doc.get_first(anchors_field).and_then(|f| f.as_text()).unwrap_or_default();
+
+    if (new_authority - old_authority).abs() < EPSILON && new_anchors == old_anchors {
+      unchanged.entry(url_id).or_default().push(doc);
+      continue;
+    }
+
+    let mut updated = Document::from(
+      doc.field_values().iter()
+        .filter(|field_value| field_value.field() != authority_field && field_value.field() != anchors_field)
+        .cloned()
+        .collect::<Vec<_>>()
+    );
+    updated.add_field_value(authority_field, Value::F64(new_authority));
+    if !new_anchors.is_empty() {
+      updated.add_text(anchors_field, new_anchors);
+    }
+    changed.entry(url_id).or_default().push(updated);
+  }
+
+  let mut writer = index.writer(50_000_000)?;
+  let mut num_updated = 0;
+  for (url_id, docs) in changed {
+    writer.delete_term(Term::from_field_bytes(url_id_field, &url_id));
+    for mut doc in docs.into_iter().chain(unchanged.remove(&url_id).into_iter().flatten()) {
+      schema::add_derived_fields(index, &mut doc);
+      writer.add_document(doc)?;
+      num_updated += 1;
+    }
+  }
+  writer.commit()?;
+
+  Ok(num_updated)
+}
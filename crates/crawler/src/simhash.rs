@@ -0,0 +1,67 @@
+// Number of consecutive words hashed together. Shingles keep word order significant,
+// so pages using the same vocabulary differently don't look alike
+const SHINGLE_SIZE: usize = 3;
+
+// 64 bit FNV-1a, stable across builds unlike `DefaultHasher`
+fn fnv1a(text: &str) -> u64 {
+  text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+// SimHash fingerprint of a page's text. Near-duplicate texts get fingerprints which differ in only
+// a few bits, see `config::NEAR_DUPLICATE_DISTANCE`. Returns `None` when there is no text
+pub fn simhash(text: &str) -> Option<u64> {
+  let words: Vec<String> = text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(|word| word.to_lowercase())
+    .collect();
+  if words.is_empty() {
+    return None;
+  }
+
+  let mut weights = [0i64; 64];
+  for shingle in words.windows(SHINGLE_SIZE.min(words.len())) {
+    let hash = fnv1a(&shingle.join(" "));
+    for (bit, weight) in weights.iter_mut().enumerate() {
+      *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+    }
+  }
+
+  Some(weights.iter().enumerate()
+    .filter(|(_, weight)| **weight > 0)
+    .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit))
+}
+
+#[cfg(test)]
+mod tests {
+  use config::NEAR_DUPLICATE_DISTANCE;
+
+  use super::*;
+
+  const PAGE: &str = "Vectors are re-sizable arrays. Like slices, their size is not known at compile time, but they can grow or shrink at any time. A vector is represented using 3 parameters: pointer to the data, length and capacity. The capacity indicates how much memory is reserved for the vector.";
+
+  fn distance(a: &str, b: &str) -> u32 {
+    (simhash(a).unwrap() ^ simhash(b).unwrap()).count_ones()
+  }
+
+  #[test]
+  fn fingerprints_ignore_case_and_punctuation() {
+    assert_eq!(simhash(PAGE), simhash(&PAGE.to_uppercase().replace(['.', ',', ':'], " ")));
+    assert_eq!(simhash(" \n -- "), None);
+  }
+
+  #[test]
+  fn near_duplicates_are_within_the_distance() {
+    let print_view = format!("{} Print this page", PAGE);
+    assert!(distance(PAGE, &print_view) <= NEAR_DUPLICATE_DISTANCE);
+
+    let other_page = "Strings are UTF-8 encoded growable buffers. A String is made up of a pointer to the heap, a length and a capacity, and can be built from a string literal with String::from or to_string.";
+    assert!(distance(PAGE, other_page) > NEAR_DUPLICATE_DISTANCE);
+  }
+
+  #[test]
+  fn short_texts_are_hashed_as_a_single_shingle() {
+    assert_eq!(simhash("Vectors"), Some(fnv1a("vectors")));
+    assert_eq!(simhash("Growable vectors"), Some(fnv1a("growable vectors")));
+  }
+}
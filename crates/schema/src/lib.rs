@@ -0,0 +1,131 @@
+mod snapshots;
+mod tokenizers;
+
+use std::path::Path;
+
+use tantivy::{
+  schema::{Cardinality, DateOptions, DatePrecision, Document, FacetOptions, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, STORED, STRING, TEXT},
+  tokenizer::{PreTokenizedString, Token},
+  Index,
+};
+
+pub use snapshots::{LockedSnapshot, SnapshotStore, Staging};
+pub use tokenizers::{register_tokenizers, stem_tokenizer_for_language, STEM_TOKENIZER};
+
+// Fields analyzed with a stemming analyzer, as ( stored exact-form field, stemmed field )
+pub const STEMMED_FIELDS: [(&str, &str); 4] = [
+  ("title", "title_stem"),
+  ("headings", "headings_stem"),
+  ("content", "content_stem"),
+  ("anchors", "anchors_stem"),
+];
+
+// Schema shared by the crawler ( writing ) and the searcher ( reading )
+pub fn build_schema() -> Schema {
+  let mut schema_builder = Schema::builder();
+
+  // Exact-form text, lower cased but not stemmed. Used for phrase and identifier matching
+  schema_builder.add_text_field("title", TEXT | STORED);
+  schema_builder.add_text_field("content", TEXT | STORED);
+  schema_builder.add_text_field("url", STORED | TEXT);
+  schema_builder.add_bytes_field("url_id", STORED | INDEXED);
+
+  // TODO: Use a facet for this field
+  schema_builder.add_text_field("domain", STORED | TEXT);
+  schema_builder.add_text_field("headings", TEXT | STORED);
+
+  let date_field_opts = DateOptions::from(INDEXED).set_stored().set_precision(DatePrecision::Milliseconds);
+  schema_builder.add_date_field("scraped_at", date_field_opts.clone());
+
+  // Last modification date of the page ( header / meta tags, `scraped_at` if unknown ). Fast for ranking and sorting
+  schema_builder.add_date_field("last_modified", date_field_opts.set_fast(Cardinality::SingleValue));
+  schema_builder.add_facet_field("lang", FacetOptions::default().set_stored());
+  // Configured site the page was crawled from ( `/doc.rust-lang.org` ), used for per-site boosts
+  schema_builder.add_facet_field("site", FacetOptions::default().set_stored());
+
+  // Documentation version detected from the url, see `config::VERSION_PATTERNS`
+  schema_builder.add_text_field("product", STRING | STORED);
+  schema_builder.add_text_field("version", STRING | STORED);
+  schema_builder.add_text_field("page_key", STRING | STORED);
+
+  // Language of the page from `<html lang>`, decides the analyzer of the stemmed fields
+  schema_builder.add_text_field("page_lang", STRING | STORED);
+
+  // PageRank of the page within its site ( 0 - 1 ), computed from the link graph after crawling
+  schema_builder.add_f64_field("authority", FAST | STORED);
+  // Text of the links pointing to the page from other pages of its site, one anchor per line
+  schema_builder.add_text_field("anchors", TEXT | STORED);
+
+  // SimHash fingerprint of the content, used to collapse near-duplicate pages in results
+  schema_builder.add_u64_field("simhash", STORED);
+
+  // Stemmed copies of the exact-form fields. Not stored, they are derived from the stored fields
+  let stemmed_opts = TextOptions::default().set_indexing_options(
+    TextFieldIndexing::default()
+      .set_tokenizer(STEM_TOKENIZER)
+      .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+  );
+  for (_, stemmed_field) in STEMMED_FIELDS {
+    schema_builder.add_text_field(stemmed_field, stemmed_opts.clone());
+  }
+
+  schema_builder.build()
+}
+
+// Opens the index at `path` with the custom tokenizers registered
+pub fn open_index<P: AsRef<Path>>(path: P) -> tantivy::Result<Index> {
+  let index = Index::open_in_dir(path)?;
+  register_tokenizers(&index);
+  Ok(index)
+}
+
+// Opens the index at `path`, creating it ( and its directory ) with the shared schema when it doesn't exist yet
+pub fn open_or_create_index<P: AsRef<Path>>(path: P) -> tantivy::Result<Index> {
+  std::fs::create_dir_all(path.as_ref())?;
+  let index_dir = tantivy::directory::MmapDirectory::open(path.as_ref())?;
+  let index = if Index::exists(&index_dir)? {
+    Index::open(index_dir)?
+  } else {
+    Index::create_in_dir(path, build_schema())?
+  };
+
+  register_tokenizers(&index);
+  Ok(index)
+}
+
+// Adds the fields which are not stored but derived from the stored ones ( the stemmed copies ).
+// Pages in a language other than English are analyzed with the analyzer of their language
+pub fn add_derived_fields(index: &Index, doc: &mut Document) {
+  let schema = index.schema();
+  let page_lang = schema.get_field("page_lang")
+    .and_then(|field| doc.get_first(field))
+    .and_then(|value| value.as_text())
+    .map(|lang| lang.to_string());
+
+  let analyzer = page_lang.as_deref()
+    .and_then(stem_tokenizer_for_language)
+    .filter(|tokenizer| tokenizer != STEM_TOKENIZER)
+    .and_then(|tokenizer| index.tokenizers().get(&tokenizer));
+
+  for (field_name, stemmed_field_name) in STEMMED_FIELDS {
+    let (Some(field), Some(stemmed_field)) = (schema.get_field(field_name), schema.get_field(stemmed_field_name)) else {
+      continue;
+    };
+
+    let texts: Vec<String> = doc.get_all(field)
+      .filter_map(|value| value.as_text())
+      .map(|text| text.to_string())
+      .collect();
+
+    for text in texts {
+      match &analyzer {
+        Some(analyzer) => {
+          let mut tokens: Vec<Token> = Vec::new();
+          analyzer.token_stream(&text).process(&mut |token: &Token| tokens.push(token.clone()));
+          doc.add_pre_tokenized_text(stemmed_field, PreTokenizedString { text, tokens });
+        },
+        None => doc.add_text(stemmed_field, text),
+      }
+    }
+  }
+}
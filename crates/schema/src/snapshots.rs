@@ -0,0 +1,345 @@
+use std::{fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use fs2::FileExt;
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+const STAGING_DIR: &str = "staging";
+const CURRENT_FILE: &str = "CURRENT";
+const LOCK_FILE: &str = "snapshots.lock";
+
+// Index directory holding published snapshots of the index. Writers build a snapshot in `staging/`
+// and publish it by moving it to `snapshots/` and replacing the `CURRENT` pointer file, so readers
+// only ever open complete snapshots. An index directory without `CURRENT` ( created before
+// snapshots ) is served as is until the first snapshot is published
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+  root: PathBuf,
+}
+
+// Snapshot being built. Holds the store's lock until it is published or discarded, so only one crawl
+// or import builds a snapshot at a time and none is built from a snapshot which was replaced meanwhile
+#[derive(Debug)]
+pub struct Staging {
+  path: PathBuf,
+  _lock: File,
+}
+
+impl Staging {
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+// Current snapshot, changed in place ( pages deleted, segments merged ). Holds the store's lock until
+// dropped, so no crawl or import copies the snapshot meanwhile and publishes its copy without the changes
+#[derive(Debug)]
+pub struct LockedSnapshot {
+  path: PathBuf,
+  _lock: File,
+}
+
+impl LockedSnapshot {
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as u64).unwrap_or_default()
+}
+
+// Files written by tantivy: segment files ( `<uuid>.<ext>`, `<uuid>.<opstamp>.del` ) and its meta
+// files. They are never modified, only replaced, so they can be shared between snapshots
+fn is_index_file(name: &str) -> bool {
+  let segment_id = name.split('.').next().unwrap_or_default();
+  name == "meta.json" || name == ".managed.json"
+    || (segment_id.len() == 32 && segment_id.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl SnapshotStore {
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    Self { root: root.into() }
+  }
+
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  fn snapshot_path(&self, id: &str) -> PathBuf {
+    self.root.join(SNAPSHOTS_DIR).join(id)
+  }
+
+  // Exclusive lock of the store, released when the returned file is dropped ( or the process exits )
+  fn lock(&self) -> io::Result<File> {
+    fs::create_dir_all(&self.root)?;
+    let lock = File::create(self.root.join(LOCK_FILE))?;
+    lock.try_lock_exclusive().map_err(|_| {
+      io::Error::new(io::ErrorKind::WouldBlock, format!("Another crawl or import is building a snapshot of {}", self.root.display()))
+    })?;
+    Ok(lock)
+  }
+
+  // Id of the snapshot being served, `None` before the first one is published
+  pub fn current_id(&self) -> io::Result<Option<String>> {
+    match fs::read_to_string(self.root.join(CURRENT_FILE)) {
+      Ok(id) => Ok(Some(id.trim().to_string()).filter(|id| !id.is_empty())),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+
+  // Directory of the index to read, the current snapshot or the root itself
+  pub fn current_path(&self) -> io::Result<PathBuf> {
+    Ok(match self.current_id()? {
+      Some(id) => self.snapshot_path(&id),
+      None => self.root.clone(),
+    })
+  }
+
+  // Locks the current snapshot for changes made in place, failing while a snapshot is being built
+  pub fn lock_current(&self) -> io::Result<LockedSnapshot> {
+    let lock = self.lock()?;
+    Ok(LockedSnapshot { path: self.current_path()?, _lock: lock })
+  }
+
+  // Published snapshots, oldest first. Ids are the publication time in milliseconds
+  pub fn list(&self) -> io::Result<Vec<String>> {
+    let mut ids = match fs::read_dir(self.root.join(SNAPSHOTS_DIR)) {
+      Ok(entries) => entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|id| id.parse::<u64>().is_ok())
+        .collect::<Vec<_>>(),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+      Err(e) => return Err(e),
+    };
+    ids.sort_by_key(|id| id.parse::<u64>().unwrap_or_default());
+    Ok(ids)
+  }
+
+  // Creates the staging directory of a new snapshot, failing when another one is being built. With
+  // `from_current` it starts as a copy of the current index. The index files are hard linked when
+  // possible, the other files ( the link graph ) are appended to and are copied
+  pub fn stage(&self, from_current: bool) -> io::Result<Staging> {
+    let lock = self.lock()?;
+    let staging = self.root.join(STAGING_DIR);
+    // Left over by a writer which was killed, nothing else builds a snapshot while we hold the lock
+    if staging.exists() {
+      fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir(&staging)?;
+
+    if from_current {
+      let current = self.current_path()?;
+      let entries = match fs::read_dir(&current) {
+        Ok(entries) => entries.collect::<io::Result<Vec<_>>>()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+      };
+      for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Skips the snapshot directories, pointer and lock of the root, and the lock files of writers
+        if !entry.file_type()?.is_file() || name.starts_with(CURRENT_FILE) || name.ends_with(".lock") {
+          continue;
+        }
+        let target = staging.join(&name);
+        if !is_index_file(&name) || fs::hard_link(entry.path(), &target).is_err() {
+          fs::copy(entry.path(), &target)?;
+        }
+      }
+    }
+
+    Ok(Staging { path: staging, _lock: lock })
+  }
+
+  // Publishes a staged snapshot as the current one and removes the oldest snapshots beyond `keep`.
+  // Returns the id of the new snapshot, its publication time
+  pub fn publish(&self, staging: Staging, keep: usize) -> io::Result<String> {
+    // Later than every published snapshot, even when the clock went back
+    let last_id = self.list()?.last().and_then(|id| id.parse::<u64>().ok()).unwrap_or_default();
+    let id = now_ms().max(last_id + 1).to_string();
+
+    fs::create_dir_all(self.root.join(SNAPSHOTS_DIR))?;
+    fs::rename(&staging.path, self.snapshot_path(&id))?;
+    self.set_current(&id)?;
+    self.prune(keep)?;
+    Ok(id)
+  }
+
+  // Removes a staged snapshot which won't be published
+  pub fn discard(&self, staging: Staging) -> io::Result<()> {
+    fs::remove_dir_all(&staging.path)
+  }
+
+  // Serves the snapshot `id`, or the one published before the current snapshot without `id`. Fails
+  // while a snapshot is being built, it would be published on top of the rolled back one
+  pub fn rollback(&self, id: Option<&str>) -> io::Result<String> {
+    let _lock = self.lock()?;
+    let ids = self.list()?;
+    let target = match id {
+      Some(id) => ids.iter().find(|snapshot| *snapshot == id)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No snapshot `{}`", id)))?,
+      None => {
+        let current = self.current_id()?;
+        let position = ids.iter().position(|id| Some(id) == current.as_ref()).unwrap_or(ids.len());
+        ids[..position].last()
+          .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No snapshot older than the current one"))?
+      },
+    };
+    self.set_current(target)?;
+    Ok(target.clone())
+  }
+
+  // Replaces the pointer file atomically, readers see either the previous or the new snapshot
+  fn set_current(&self, id: &str) -> io::Result<()> {
+    let pointer_tmp = self.root.join(format!("{}.tmp", CURRENT_FILE));
+    let mut file = File::create(&pointer_tmp)?;
+    file.write_all(id.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(pointer_tmp, self.root.join(CURRENT_FILE))
+  }
+
+  // Removes the oldest snapshots so at most `keep` are left, never the current one
+  fn prune(&self, keep: usize) -> io::Result<()> {
+    let current = self.current_id()?;
+    let ids = self.list()?;
+    let num_removed = ids.len().saturating_sub(keep.max(1));
+    for id in ids.iter().take(num_removed).filter(|id| Some(*id) != current.as_ref()) {
+      fs::remove_dir_all(self.snapshot_path(id))?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Publishes a snapshot holding one file with `content`
+  fn publish(store: &SnapshotStore, content: &str, keep: usize) -> String {
+    let staging = store.stage(false).unwrap();
+    fs::write(staging.path().join("links.jsonl"), content).unwrap();
+    store.publish(staging, keep).unwrap()
+  }
+
+  fn current_content(store: &SnapshotStore) -> String {
+    fs::read_to_string(store.current_path().unwrap().join("links.jsonl")).unwrap()
+  }
+
+  #[test]
+  fn serves_the_root_before_the_first_publish() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+
+    assert_eq!(store.current_id().unwrap(), None);
+    assert_eq!(store.current_path().unwrap(), dir.path());
+    assert!(store.list().unwrap().is_empty());
+  }
+
+  #[test]
+  fn only_one_snapshot_is_built_at_a_time() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+
+    let staging = store.stage(false).unwrap();
+    assert_eq!(store.stage(true).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(store.rollback(None).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(store.lock_current().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+    store.discard(staging).unwrap();
+    let staging = store.stage(true).unwrap();
+    let id = store.publish(staging, 3).unwrap();
+    assert!(store.stage(true).is_ok());
+
+    // Nothing is staged from the current snapshot while it is changed in place
+    let current = store.lock_current().unwrap();
+    assert_eq!(current.path(), store.snapshot_path(&id));
+    assert_eq!(store.stage(true).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    drop(current);
+    assert!(store.stage(true).is_ok());
+  }
+
+  #[test]
+  fn ids_follow_the_publication_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+
+    // Published within the same millisecond
+    let ids: Vec<String> = (0..3).map(|i| publish(&store, &i.to_string(), 5)).collect();
+    assert_eq!(store.list().unwrap(), ids);
+    assert_eq!(store.current_id().unwrap().as_ref(), ids.last());
+
+    // A snapshot staged before another one was published still gets a later id
+    let staging = store.stage(true).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let id = store.publish(staging, 5).unwrap();
+    assert!(id.parse::<u64>().unwrap() > ids[2].parse::<u64>().unwrap());
+  }
+
+  #[test]
+  fn stages_a_copy_of_the_current_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let first = publish(&store, "first", 3);
+
+    let staging = store.stage(true).unwrap();
+    let segment = "0123456789abcdef0123456789abcdef.store";
+    fs::write(store.snapshot_path(&first).join(segment), "segment").unwrap();
+    drop(staging);
+
+    // Writing to the staged copy of the link graph leaves the published one untouched
+    let staging = store.stage(true).unwrap();
+    assert!(staging.path().join(segment).exists());
+    fs::OpenOptions::new().append(true).open(staging.path().join("links.jsonl")).unwrap().write_all(b" more").unwrap();
+    assert_eq!(current_content(&store), "first");
+
+    store.publish(staging, 3).unwrap();
+    assert_eq!(current_content(&store), "first more");
+  }
+
+  #[test]
+  fn discarded_snapshots_are_never_served() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    publish(&store, "first", 3);
+
+    let staging = store.stage(true).unwrap();
+    fs::write(staging.path().join("links.jsonl"), "failed crawl").unwrap();
+    store.discard(staging).unwrap();
+
+    assert_eq!(current_content(&store), "first");
+    assert_eq!(store.list().unwrap().len(), 1);
+    assert!(!dir.path().join(STAGING_DIR).exists());
+  }
+
+  #[test]
+  fn rolls_back_to_the_previously_published_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let a = publish(&store, "a", 5);
+    let b = publish(&store, "b", 5);
+
+    assert_eq!(store.rollback(None).unwrap(), a);
+    assert_eq!(current_content(&store), "a");
+    assert_eq!(store.rollback(None).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+    // Published on top of the rolled back snapshot, the previous one is still `b`
+    let c = publish(&store, "c", 5);
+    assert_eq!(store.list().unwrap(), vec![a.clone(), b.clone(), c]);
+    assert_eq!(store.rollback(None).unwrap(), b);
+    assert_eq!(store.rollback(Some(&a)).unwrap(), a);
+    assert_eq!(store.rollback(Some("1")).unwrap_err().kind(), io::ErrorKind::NotFound);
+  }
+
+  #[test]
+  fn prunes_the_oldest_snapshots() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let ids: Vec<String> = (0..4).map(|i| publish(&store, &i.to_string(), 2)).collect();
+    assert_eq!(store.list().unwrap(), ids[2..]);
+
+    // Pruned by publication order, not by the snapshot they were staged from
+    store.rollback(Some(&ids[2])).unwrap();
+    let newest = publish(&store, "newest", 2);
+    assert_eq!(store.list().unwrap(), vec![ids[3].clone(), newest]);
+  }
+}
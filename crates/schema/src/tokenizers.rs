@@ -0,0 +1,56 @@
+use tantivy::{tokenizer::{Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer, StopWordFilter, TextAnalyzer}, Index};
+
+// Tokenizer of the `*_stem` fields, also used to analyze queries against them
+pub const STEM_TOKENIZER: &str = "stem_en";
+
+// Page languages ( `<html lang>` ) with a stemming analyzer, registered as `stem_<code>`
+const LANGUAGES: [(&str, Language); 18] = [
+  ("ar", Language::Arabic),
+  ("da", Language::Danish),
+  ("de", Language::German),
+  ("el", Language::Greek),
+  ("en", Language::English),
+  ("es", Language::Spanish),
+  ("fi", Language::Finnish),
+  ("fr", Language::French),
+  ("hu", Language::Hungarian),
+  ("it", Language::Italian),
+  ("nb", Language::Norwegian),
+  ("nl", Language::Dutch),
+  ("no", Language::Norwegian),
+  ("pt", Language::Portuguese),
+  ("ro", Language::Romanian),
+  ("ru", Language::Russian),
+  ("sv", Language::Swedish),
+  ("tr", Language::Turkish),
+];
+
+fn stem_analyzer(language: Language) -> TextAnalyzer {
+  let analyzer = TextAnalyzer::from(SimpleTokenizer)
+    .filter(RemoveLongFilter::limit(40))
+    .filter(LowerCaser);
+
+  let analyzer = match StopWordFilter::new(language) {
+    Some(stop_words) => analyzer.filter(stop_words),
+    None => analyzer,
+  };
+
+  analyzer.filter(Stemmer::new(language))
+}
+
+// Registers the stemming analyzers. Needs to be called on every opened `Index`,
+// as custom tokenizers are not persisted with the index
+pub fn register_tokenizers(index: &Index) {
+  for (code, language) in LANGUAGES {
+    index.tokenizers().register(&format!("stem_{}", code), stem_analyzer(language));
+  }
+}
+
+// Name of the stemming analyzer for a page language, e.g. `de-AT` -> `stem_de`
+pub fn stem_tokenizer_for_language(page_lang: &str) -> Option<String> {
+  let code = page_lang.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+
+  LANGUAGES.iter()
+    .find(|(lang_code, _)| *lang_code == code)
+    .map(|(lang_code, _)| format!("stem_{}", lang_code))
+}
@@ -0,0 +1,51 @@
+use searcher::{DocSearcher, Evaluation, SearchOptions};
+
+// Scores the rankings against a judgment file, optionally comparing two ranking configurations:
+// cargo run --example evaluate -- <index> <judgments.tsv> [config_a] [config_b]
+fn main () {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.len() < 2 || args.len() > 4 {
+    eprintln!("Usage: evaluate <index> <judgments.tsv> [config_a] [config_b]");
+    std::process::exit(1);
+  }
+
+  let index_path = schema::SnapshotStore::new(&args[0]).current_path().unwrap();
+  let doc_searcher = DocSearcher::new(index_path.to_string_lossy().into_owned()).unwrap();
+  let judgments = searcher::parse_judgments(&std::fs::read_to_string(&args[1]).unwrap()).unwrap();
+
+  // Without a config file the defaults from the config crate are used
+  let load_options = |path: Option<&String>| match path {
+    Some(path) => SearchOptions::from_overrides(&std::fs::read_to_string(path).unwrap()).unwrap(),
+    None => SearchOptions::default(),
+  };
+  let a = doc_searcher.evaluate(&judgments, &load_options(args.get(2))).unwrap();
+
+  let Some(config_b) = args.get(3) else {
+    println!("{:<40} {:>8} {:>8} {:>8}", "query", "ndcg@10", "rr", "recall");
+    for query in &a.queries {
+      println!("{:<40} {:>8.3} {:>8.3} {:>8.3}", query.query, query.ndcg, query.reciprocal_rank, query.recall);
+    }
+    println!("{:<40} {:>8.3} {:>8.3} {:>8.3}", "mean", a.ndcg, a.mrr, a.recall);
+    return;
+  };
+
+  let b = doc_searcher.evaluate(&judgments, &load_options(Some(config_b))).unwrap();
+  print_diff(&a, &b);
+}
+
+// Side by side nDCG@10 per query, the queries which changed most first
+fn print_diff(a: &Evaluation, b: &Evaluation) {
+  let mut queries: Vec<_> = a.queries.iter().zip(&b.queries).collect();
+  queries.sort_by(|(a1, b1), (a2, b2)| (b2.ndcg - a2.ndcg).abs().total_cmp(&(b1.ndcg - a1.ndcg).abs()));
+
+  println!("{:<40} {:>8} {:>8} {:>8}", "query", "a", "b", "delta");
+  for (query_a, query_b) in queries {
+    println!("{:<40} {:>8.3} {:>8.3} {:>+8.3}", query_a.query, query_a.ndcg, query_b.ndcg, query_b.ndcg - query_a.ndcg);
+  }
+
+  println!();
+  println!("{:<40} {:>8} {:>8} {:>8}", "metric", "a", "b", "delta");
+  for (metric, value_a, value_b) in [("ndcg@10", a.ndcg, b.ndcg), ("mrr", a.mrr, b.mrr), ("recall@10", a.recall, b.recall)] {
+    println!("{:<40} {:>8.3} {:>8.3} {:>+8.3}", metric, value_a, value_b, value_b - value_a);
+  }
+}
@@ -1,7 +1,8 @@
 use searcher::DocSearcher;
 
 fn main () {
-  let doc_searcher = DocSearcher::new("./index".to_string()).unwrap();
+  let index_path = schema::SnapshotStore::new(config::INDEX_PATH).current_path().unwrap();
+  let doc_searcher = DocSearcher::new(index_path.to_string_lossy().into_owned()).unwrap();
   let result = doc_searcher.get_pages_per_site();
   println!("Result: {:?}", result);
 }
\ No newline at end of file
@@ -0,0 +1,154 @@
+use tantivy::{query::{BooleanQuery, BoostQuery, Occur, PhraseQuery, Query, TermQuery}, schema::IndexRecordOption, Score, Term};
+
+// Reads the debug representation of a query, taking its terms from `Query::query_terms`, which visits
+// them in the same order
+struct DebugReader<'a> {
+  rest: &'a str,
+  terms: std::vec::IntoIter<Term>,
+}
+
+impl<'a> DebugReader<'a> {
+  fn eat(&mut self, prefix: &str) -> bool {
+    match self.rest.strip_prefix(prefix) {
+      Some(rest) => {
+        self.rest = rest;
+        true
+      },
+      None => false,
+    }
+  }
+
+  fn expect(&mut self, prefix: &str) -> Option<()> {
+    self.eat(prefix).then_some(())
+  }
+
+  // Text up to `end`, which is consumed as well
+  fn until(&mut self, end: char) -> Option<&'a str> {
+    let (text, rest) = self.rest.split_once(end)?;
+    self.rest = rest;
+    Some(text)
+  }
+
+  // Skips `Term(..)`, whose value is quoted when it is text
+  fn term(&mut self) -> Option<Term> {
+    self.expect("Term(")?;
+    let mut depth = 1;
+    let mut quoted = false;
+    let mut escaped = false;
+    let end = self.rest.char_indices().find_map(|(i, c)| {
+      match c {
+        _ if escaped => escaped = false,
+        '\\' if quoted => escaped = true,
+        '"' => quoted = !quoted,
+        '(' if !quoted => depth += 1,
+        ')' if !quoted => {
+          depth -= 1;
+          if depth == 0 {
+            return Some(i + 1);
+          }
+        },
+        _ => {},
+      }
+      None
+    })?;
+    self.rest = &self.rest[end..];
+    self.terms.next()
+  }
+
+  fn query(&mut self) -> Option<Box<dyn Query>> {
+    if self.eat("Boost(query=") {
+      let query = self.query()?;
+      self.expect(", boost=")?;
+      let boost = self.until(')')?.parse().ok()?;
+      return Some(Box::new(BoostQuery::new(query, boost)));
+    }
+
+    if self.eat("BooleanQuery { subqueries: [") {
+      let mut clauses = Vec::new();
+      while !self.eat("] }") {
+        self.eat(", ");
+        self.expect("(")?;
+        let occur = match self.until(',')? {
+          "Should" => Occur::Should,
+          "Must" => Occur::Must,
+          "MustNot" => Occur::MustNot,
+          _ => return None,
+        };
+        self.expect(" ")?;
+        clauses.push((occur, self.query()?));
+        self.expect(")")?;
+      }
+      return Some(Box::new(BooleanQuery::new(clauses)));
+    }
+
+    if self.eat("TermQuery(") {
+      let term = self.term()?;
+      self.expect(")")?;
+      return Some(Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)));
+    }
+
+    if self.eat("PhraseQuery { field: ") {
+      self.until('[')?;
+      let mut terms = Vec::new();
+      while !self.eat("], slop: ") {
+        self.eat(", ");
+        self.expect("(")?;
+        let offset = self.until(',')?.parse().ok()?;
+        self.expect(" ")?;
+        terms.push((offset, self.term()?));
+        self.expect(")")?;
+      }
+      let slop = self.until(' ')?.parse().ok()?;
+      self.expect("}")?;
+      return (terms.len() > 1).then(|| Box::new(PhraseQuery::new_with_offset_and_slop(terms, slop)) as Box<dyn Query>);
+    }
+
+    None
+  }
+}
+
+// Tantivy keeps the query of a `BoostQuery` private. It is rebuilt from the debug representation, so
+// explanations can explain its clauses one by one. `None` for queries other than boolean, term and
+// phrase queries, and boosts of them
+pub fn unboost(query: &BoostQuery) -> Option<(Box<dyn Query>, Score)> {
+  let mut terms = Vec::new();
+  query.query_terms(&mut |term, _| terms.push(term.clone()));
+
+  let debug = format!("{:?}", query);
+  let mut reader = DebugReader { rest: debug.strip_prefix("Boost(query=")?, terms: terms.into_iter() };
+  let inner = reader.query()?;
+  reader.expect(", boost=")?;
+  let boost = reader.until(')')?.parse().ok()?;
+  (reader.rest.is_empty() && reader.terms.next().is_none()).then_some((inner, boost))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tantivy::schema::Field;
+
+  fn term(text: &str) -> Term {
+    Term::from_field_text(Field::from_field_id(0), text)
+  }
+
+  #[test]
+  fn boosted_queries_are_rebuilt() {
+    let phrase = PhraseQuery::new_with_offset_and_slop(vec![(0, term("growable")), (2, term("array"))], 1);
+    let clauses: Vec<(Occur, Box<dyn Query>)> = vec![
+      (Occur::Should, Box::new(BoostQuery::new(Box::new(TermQuery::new(term("(quoted \"text\")"), IndexRecordOption::WithFreqs)), 3.0))),
+      (Occur::Must, Box::new(phrase)),
+      (Occur::MustNot, Box::new(BooleanQuery::new(Vec::new()))),
+    ];
+    let query = BoostQuery::new(Box::new(BooleanQuery::new(clauses)), 0.5);
+
+    let (inner, boost) = unboost(&query).unwrap();
+    assert_eq!(boost, 0.5);
+    assert_eq!(format!("{:?}", BoostQuery::new(inner, boost)), format!("{:?}", query));
+  }
+
+  #[test]
+  fn other_queries_are_not_rebuilt() {
+    let query = BoostQuery::new(Box::new(tantivy::query::AllQuery), 2.0);
+    assert!(unboost(&query).is_none());
+  }
+}
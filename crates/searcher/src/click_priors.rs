@@ -0,0 +1,61 @@
+use std::{collections::HashMap, path::Path};
+
+use config::CLICK_PRIORS_PATH;
+use tantivy::Score;
+
+// Searches differing only in case and spacing are the same query
+pub fn normalize_query(query: &str) -> String {
+  query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// How much more ( > 1 ) or less ( < 1 ) users click a url than expected from its position.
+// Priors of a url for a specific query take precedence over the url's priors across all queries
+#[derive(Debug, Clone, Default)]
+pub struct ClickPriors {
+  urls: HashMap<String, Score>,
+  query_urls: HashMap<(String, String), Score>,
+}
+
+impl ClickPriors {
+  // Priors computed by the API into `data_dir`, empty until enough clicks were logged
+  pub fn from_config(data_dir: &Path) -> Self {
+    std::fs::read_to_string(data_dir.join(CLICK_PRIORS_PATH))
+      .map(|contents| Self::parse(&contents))
+      .unwrap_or_default()
+  }
+
+  // Parses a priors file. One `query <TAB> url <TAB> boost` entry per line, an empty query applies
+  // to all queries. `#` starts a comment
+  pub fn parse(contents: &str) -> Self {
+    let mut priors = Self::default();
+    for line in contents.lines().filter(|line| !line.starts_with('#')) {
+      let parts: Vec<&str> = line.split('\t').collect();
+      let [query, url, boost] = parts[..] else {
+        continue;
+      };
+      let Ok(boost) = boost.trim().parse::<Score>() else {
+        continue;
+      };
+
+      if query.trim().is_empty() {
+        priors.urls.insert(url.trim().to_string(), boost);
+      } else {
+        priors.query_urls.insert((normalize_query(query), url.trim().to_string()), boost);
+      }
+    }
+
+    priors
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.urls.is_empty() && self.query_urls.is_empty()
+  }
+
+  // Score multiplier of `url` for `query`, 1 when nothing is known about it
+  pub fn boost(&self, query: &str, url: &str) -> Score {
+    self.query_urls.get(&(normalize_query(query), url.to_string()))
+      .or_else(|| self.urls.get(url))
+      .copied()
+      .unwrap_or(1.0)
+  }
+}
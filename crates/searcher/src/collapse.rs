@@ -0,0 +1,173 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use tantivy::{DocAddress, Document, Score};
+
+use crate::versions::compare_versions;
+
+// A search hit which hasn't been turned into a `SearchResult` yet
+pub struct Candidate {
+  pub score: Score,
+  pub doc_address: DocAddress,
+  pub doc: Document,
+  pub url: String,
+  pub domain: String,
+  pub page_key: Option<String>,
+  pub version: Option<String>,
+  pub simhash: Option<u64>,
+  // Multiplier from the click-through priors already applied to `score`
+  pub click_boost: Score,
+}
+
+pub struct CollapsedCandidate {
+  pub candidate: Candidate,
+  pub other_versions: Vec<String>,
+  // Urls of near-duplicates of the candidate ( mirrors, print views )
+  pub also_at: Vec<String>,
+  // Hits from the same site which didn't make it into the results, see `diversify`
+  pub more_from_site: usize,
+}
+
+// Groups the candidates which are versions of the same page and keeps the latest version.
+// Candidates are expected in score order, a group keeps the position of its best scoring member
+pub fn collapse_versions(candidates: Vec<Candidate>) -> Vec<CollapsedCandidate> {
+  let mut groups: Vec<CollapsedCandidate> = Vec::new();
+  let mut group_by_key: HashMap<String, usize> = HashMap::new();
+
+  for candidate in candidates {
+    let key = candidate.page_key.clone().unwrap_or_else(|| candidate.url.clone());
+
+    let Some(&idx) = group_by_key.get(&key) else {
+      group_by_key.insert(key, groups.len());
+      groups.push(CollapsedCandidate { candidate, other_versions: Vec::new(), also_at: Vec::new(), more_from_site: 0 });
+      continue;
+    };
+
+    let group = &mut groups[idx];
+    let is_newer = match (&candidate.version, &group.candidate.version) {
+      (Some(version), Some(group_version)) => compare_versions(version, group_version) == Ordering::Greater,
+      _ => false,
+    };
+
+    if is_newer {
+      let score = group.candidate.score;
+      let older = std::mem::replace(&mut group.candidate, candidate);
+      group.candidate.score = score;
+      group.other_versions.extend(older.version);
+    } else {
+      group.other_versions.extend(candidate.version);
+    }
+  }
+
+  groups
+}
+
+// Folds the candidates whose content fingerprints differ in at most `max_distance` bits into the
+// best scoring one. Candidates are expected in score order
+pub fn collapse_duplicates(candidates: Vec<CollapsedCandidate>, max_distance: u32) -> Vec<CollapsedCandidate> {
+  let mut groups: Vec<CollapsedCandidate> = Vec::new();
+
+  for candidate in candidates {
+    let duplicate_of = candidate.candidate.simhash.and_then(|simhash| {
+      groups.iter().position(|group| {
+        group.candidate.simhash.is_some_and(|group_simhash| (simhash ^ group_simhash).count_ones() <= max_distance)
+      })
+    });
+
+    match duplicate_of {
+      Some(idx) => groups[idx].also_at.push(candidate.candidate.url),
+      None => groups.push(candidate),
+    }
+  }
+
+  groups
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+
+  pub(crate) fn candidate(url: &str, score: Score) -> Candidate {
+    Candidate {
+      score,
+      doc_address: DocAddress::new(0, 0),
+      doc: Document::default(),
+      url: url.to_string(),
+      domain: url.split('/').nth(2).unwrap_or_default().to_string(),
+      page_key: None,
+      version: None,
+      simhash: None,
+      click_boost: 1.0,
+    }
+  }
+
+  fn versioned(url: &str, score: Score, page_key: &str, version: &str) -> Candidate {
+    Candidate { page_key: Some(page_key.to_string()), version: Some(version.to_string()), ..candidate(url, score) }
+  }
+
+  #[test]
+  fn versions_of_a_page_collapse_into_the_latest_at_the_best_position() {
+    let collapsed = collapse_versions(vec![
+      versioned("https://docs.python.org/3.8/library/os.html", 9.0, "python/library/os.html", "3.8"),
+      candidate("https://docs.rs/tokio", 5.0),
+      versioned("https://docs.python.org/3.12/library/os.html", 4.0, "python/library/os.html", "3.12"),
+      versioned("https://docs.python.org/3/library/os.html", 3.0, "python/library/os.html", "3"),
+    ]);
+
+    let urls: Vec<&str> = collapsed.iter().map(|group| group.candidate.url.as_str()).collect();
+    assert_eq!(urls, ["https://docs.python.org/3.12/library/os.html", "https://docs.rs/tokio"]);
+    assert_eq!(collapsed[0].candidate.score, 9.0);
+    assert_eq!(collapsed[0].other_versions, ["3.8", "3"]);
+  }
+
+  #[test]
+  fn stable_versions_are_preferred_over_numbered_ones() {
+    let collapsed = collapse_versions(vec![
+      versioned("https://doc.rust-lang.org/1.70.0/std/vec/", 2.0, "rust/std/vec/", "1.70.0"),
+      versioned("https://doc.rust-lang.org/stable/std/vec/", 1.0, "rust/std/vec/", "stable"),
+      versioned("https://doc.rust-lang.org/nightly/std/vec/", 1.0, "rust/std/vec/", "nightly"),
+    ]);
+
+    assert_eq!(collapsed.len(), 1);
+    assert_eq!(collapsed[0].candidate.url, "https://doc.rust-lang.org/stable/std/vec/");
+    assert_eq!(collapsed[0].other_versions, ["1.70.0", "nightly"]);
+  }
+
+  #[test]
+  fn pages_without_a_version_are_kept_apart() {
+    let collapsed = collapse_versions(vec![candidate("https://go.dev/doc/", 2.0), candidate("https://go.dev/blog/", 1.0)]);
+
+    assert_eq!(collapsed.len(), 2);
+    assert!(collapsed.iter().all(|group| group.other_versions.is_empty()));
+  }
+
+  fn fingerprinted(url: &str, score: Score, simhash: Option<u64>) -> CollapsedCandidate {
+    let candidate = Candidate { simhash, ..candidate(url, score) };
+    CollapsedCandidate { candidate, other_versions: Vec::new(), also_at: Vec::new(), more_from_site: 0 }
+  }
+
+  #[test]
+  fn near_duplicates_fold_into_the_best_scoring_page() {
+    let collapsed = collapse_duplicates(vec![
+      fingerprinted("https://docs.rs/serde/latest/serde/", 3.0, Some(0b1111_0000)),
+      fingerprinted("https://mirror.example.com/serde/", 2.0, Some(0b1111_0111)),
+      fingerprinted("https://serde.rs/", 1.0, Some(0b0000_1111)),
+    ], 3);
+
+    let urls: Vec<&str> = collapsed.iter().map(|group| group.candidate.url.as_str()).collect();
+    assert_eq!(urls, ["https://docs.rs/serde/latest/serde/", "https://serde.rs/"]);
+    assert_eq!(collapsed[0].also_at, ["https://mirror.example.com/serde/"]);
+  }
+
+  #[test]
+  fn pages_further_apart_than_the_distance_or_without_text_are_kept() {
+    let collapsed = collapse_duplicates(vec![
+      fingerprinted("https://docs.rs/serde/", 3.0, Some(0)),
+      fingerprinted("https://serde.rs/", 2.0, Some(0b1111)),
+      fingerprinted("https://serde.rs/empty", 1.0, None),
+      fingerprinted("https://serde.rs/blank", 1.0, None),
+    ], 3);
+
+    assert_eq!(collapsed.len(), 4);
+    assert!(collapsed.iter().all(|group| group.also_at.is_empty()));
+  }
+}
@@ -0,0 +1,95 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::collapse::{Candidate, CollapsedCandidate};
+
+// Keeps the best scoring hit of every url. A page may be indexed more than once, e.g. when it was
+// crawled with different query parameters
+pub fn dedupe_urls(candidates: Vec<Candidate>) -> Vec<Candidate> {
+  let mut seen = HashSet::new();
+  candidates.into_iter()
+    .filter(|candidate| seen.insert(candidate.url.clone()))
+    .collect()
+}
+
+// Returns the top `limit` candidates with at most `max_per_site` from each site. The hits over the
+// cap are moved below the other sites' hits instead of dropped, so a query matching a single site
+// still returns `limit` results. The first result of a site counts the site's hits left out
+pub fn diversify(candidates: Vec<CollapsedCandidate>, max_per_site: usize, limit: usize) -> Vec<CollapsedCandidate> {
+  let mut per_site: HashMap<String, usize> = HashMap::new();
+  let (mut selected, overflow): (Vec<_>, Vec<_>) = candidates.into_iter()
+    .partition(|candidate| {
+      let count = per_site.entry(candidate.candidate.domain.clone()).or_default();
+      *count += 1;
+      *count <= max_per_site
+    });
+
+  selected.extend(overflow);
+  let left_out = selected.split_off(limit.min(selected.len()));
+
+  let mut more_per_site: HashMap<String, usize> = HashMap::new();
+  for candidate in left_out {
+    *more_per_site.entry(candidate.candidate.domain).or_default() += 1;
+  }
+  for candidate in selected.iter_mut() {
+    if let Some(more) = more_per_site.remove(&candidate.candidate.domain) {
+      candidate.more_from_site = more;
+    }
+  }
+
+  selected
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::collapse::tests::candidate;
+
+  use super::*;
+
+  fn hits(urls: &[&str]) -> Vec<CollapsedCandidate> {
+    urls.iter().enumerate()
+      .map(|(rank, url)| CollapsedCandidate {
+        candidate: candidate(url, (urls.len() - rank) as f32),
+        other_versions: Vec::new(),
+        also_at: Vec::new(),
+        more_from_site: 0,
+      })
+      .collect()
+  }
+
+  fn urls(candidates: &[CollapsedCandidate]) -> Vec<&str> {
+    candidates.iter().map(|candidate| candidate.candidate.url.as_str()).collect()
+  }
+
+  #[test]
+  fn hits_over_the_cap_move_below_other_sites_in_score_order() {
+    let diversified = diversify(hits(&["https://a.com/1", "https://a.com/2", "https://a.com/3", "https://b.com/1", "https://a.com/4"]), 2, 10);
+
+    assert_eq!(urls(&diversified), ["https://a.com/1", "https://a.com/2", "https://b.com/1", "https://a.com/3", "https://a.com/4"]);
+    assert!(diversified.iter().all(|candidate| candidate.more_from_site == 0));
+  }
+
+  #[test]
+  fn first_result_of_a_site_counts_the_hits_left_out() {
+    let diversified = diversify(hits(&["https://a.com/1", "https://a.com/2", "https://a.com/3", "https://b.com/1", "https://c.com/1", "https://b.com/2"]), 1, 3);
+
+    assert_eq!(urls(&diversified), ["https://a.com/1", "https://b.com/1", "https://c.com/1"]);
+    let more: Vec<usize> = diversified.iter().map(|candidate| candidate.more_from_site).collect();
+    assert_eq!(more, [2, 1, 0]);
+  }
+
+  #[test]
+  fn single_site_queries_still_fill_the_limit() {
+    let diversified = diversify(hits(&["https://a.com/1", "https://a.com/2", "https://a.com/3"]), 1, 2);
+
+    assert_eq!(urls(&diversified), ["https://a.com/1", "https://a.com/2"]);
+    assert_eq!(diversified[0].more_from_site, 1);
+  }
+
+  #[test]
+  fn dedupe_keeps_the_best_hit_of_a_url() {
+    let deduped = dedupe_urls(vec![candidate("https://a.com/1", 3.0), candidate("https://a.com/2", 2.0), candidate("https://a.com/1", 1.0)]);
+
+    let urls: Vec<(&str, f32)> = deduped.iter().map(|candidate| (candidate.url.as_str(), candidate.score)).collect();
+    assert_eq!(urls, [("https://a.com/1", 3.0), ("https://a.com/2", 2.0)]);
+  }
+}
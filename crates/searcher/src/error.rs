@@ -0,0 +1,16 @@
+use tantivy::{query::QueryParserError, TantivyError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+  #[error("No index found at {0}, it is created by the first crawl")]
+  IndexNotFound(String),
+  // The index was created with an older schema, it has to be rebuilt
+  #[error("Field `{0}` is missing from the index schema")]
+  MissingField(&'static str),
+  #[error("Unknown search field: {0}")]
+  UnknownField(String),
+  #[error("Invalid query: {0}")]
+  InvalidQuery(#[from] QueryParserError),
+  #[error("Index error: {0}")]
+  Index(#[from] TantivyError),
+}
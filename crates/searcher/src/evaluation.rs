@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use crate::{DocSearcher, SearchError, SearchOptions};
+
+// Metrics are computed over the first page of results
+pub const EVALUATION_DEPTH: usize = 10;
+
+// Graded relevance of urls for a query. Grades go from 1 ( somewhat relevant ) to 3 ( the answer ),
+// urls which aren't listed are not relevant
+#[derive(Debug, Clone)]
+pub struct Judgment {
+  pub query: String,
+  pub grades: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryEvaluation {
+  pub query: String,
+  pub ndcg: f64,
+  pub reciprocal_rank: f64,
+  pub recall: f64,
+  pub urls: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+  pub queries: Vec<QueryEvaluation>,
+  pub ndcg: f64,
+  pub mrr: f64,
+  pub recall: f64,
+}
+
+// Urls are compared without the trailing slash, `/std/vec/` and `/std/vec` are the same page
+fn normalize_url(url: &str) -> String {
+  url.trim_end_matches('/').to_string()
+}
+
+// Parses a judgment file. One `query <TAB> url <TAB> grade` entry per line, lines starting with `#`
+// are comments ( urls and queries like `c#` can contain it ). Lines of the same query are merged
+pub fn parse_judgments(contents: &str) -> Result<Vec<Judgment>, String> {
+  let mut judgments: Vec<Judgment> = Vec::new();
+
+  for (line_number, line) in contents.lines().enumerate() {
+    if line.trim().is_empty() || line.trim_start().starts_with('#') {
+      continue;
+    }
+
+    let parts: Vec<&str> = line.split('\t').map(|part| part.trim()).collect();
+    let [query, url, grade] = parts[..] else {
+      return Err(format!("Line {}: expected `query <TAB> url <TAB> grade`", line_number + 1));
+    };
+    let grade = grade.parse::<u32>()
+      .map_err(|_| format!("Line {}: invalid grade `{}`", line_number + 1, grade))?;
+
+    let judgment = match judgments.iter_mut().position(|judgment| judgment.query == query) {
+      Some(idx) => &mut judgments[idx],
+      None => {
+        judgments.push(Judgment { query: query.to_string(), grades: HashMap::new() });
+        judgments.last_mut().unwrap()
+      },
+    };
+    judgment.grades.insert(normalize_url(url), grade);
+  }
+
+  Ok(judgments)
+}
+
+// Normalized discounted cumulative gain of the ranked urls, 1 when they are in the ideal order
+pub fn ndcg(urls: &[String], grades: &HashMap<String, u32>, depth: usize) -> f64 {
+  let dcg = |grades: &mut dyn Iterator<Item = u32>| -> f64 {
+    grades.take(depth)
+      .enumerate()
+      .map(|(rank, grade)| (2f64.powi(grade as i32) - 1.0) / (rank as f64 + 2.0).log2())
+      .fold(0.0, |dcg, gain| dcg + gain)
+  };
+
+  let mut ideal_grades: Vec<u32> = grades.values().copied().collect();
+  ideal_grades.sort_unstable_by(|a, b| b.cmp(a));
+  let ideal = dcg(&mut ideal_grades.into_iter());
+  if ideal == 0.0 {
+    return 0.0;
+  }
+
+  dcg(&mut urls.iter().map(|url| grades.get(url).copied().unwrap_or_default())) / ideal
+}
+
+// 1 / rank of the first relevant url, 0 when none is found
+pub fn reciprocal_rank(urls: &[String], grades: &HashMap<String, u32>, depth: usize) -> f64 {
+  urls.iter()
+    .take(depth)
+    .position(|url| grades.get(url).is_some_and(|grade| *grade > 0))
+    .map(|rank| 1.0 / (rank as f64 + 1.0))
+    .unwrap_or_default()
+}
+
+// Share of the relevant urls which were found
+pub fn recall(urls: &[String], grades: &HashMap<String, u32>, depth: usize) -> f64 {
+  let num_relevant = grades.values().filter(|grade| **grade > 0).count();
+  if num_relevant == 0 {
+    return 0.0;
+  }
+
+  let num_found = urls.iter()
+    .take(depth)
+    .filter(|url| grades.get(*url).is_some_and(|grade| *grade > 0))
+    .count();
+  num_found as f64 / num_relevant as f64
+}
+
+impl DocSearcher {
+  // Runs every judged query and scores the results, metrics are averaged over the queries
+  pub fn evaluate(&self, judgments: &[Judgment], options: &SearchOptions) -> Result<Evaluation, SearchError> {
+    let options = SearchOptions { limit: EVALUATION_DEPTH, ..options.clone() };
+
+    let mut queries = Vec::new();
+    for judgment in judgments {
+      let urls: Vec<String> = self.search(&judgment.query, &options)?
+        .results
+        .into_iter()
+        .map(|result| normalize_url(&result.url))
+        .collect();
+
+      queries.push(QueryEvaluation {
+        query: judgment.query.clone(),
+        ndcg: ndcg(&urls, &judgment.grades, EVALUATION_DEPTH),
+        reciprocal_rank: reciprocal_rank(&urls, &judgment.grades, EVALUATION_DEPTH),
+        recall: recall(&urls, &judgment.grades, EVALUATION_DEPTH),
+        urls,
+      });
+    }
+
+    let mean = |metric: fn(&QueryEvaluation) -> f64| {
+      if queries.is_empty() {
+        0.0
+      } else {
+        queries.iter().map(metric).fold(0.0, |sum, value| sum + value) / queries.len() as f64
+      }
+    };
+
+    Ok(Evaluation {
+      ndcg: mean(|query| query.ndcg),
+      mrr: mean(|query| query.reciprocal_rank),
+      recall: mean(|query| query.recall),
+      queries,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn judgments_keep_fragments_and_hashes_in_queries() {
+    let judgments = parse_judgments("# query\turl\tgrade\n\n  # indented comment\nc#\thttps://learn.microsoft.com/dotnet/csharp/\t3\ngoroutines\thttps://go.dev/doc/effective_go#goroutines\t3\ngoroutines\thttps://go.dev/tour/concurrency/1\t2\n").unwrap();
+
+    assert_eq!(judgments.len(), 2);
+    assert_eq!(judgments[0].query, "c#");
+    assert_eq!(judgments[0].grades["https://learn.microsoft.com/dotnet/csharp"], 3);
+    assert_eq!(judgments[1].grades["https://go.dev/doc/effective_go#goroutines"], 3);
+    assert_eq!(judgments[1].grades["https://go.dev/tour/concurrency/1"], 2);
+  }
+
+  #[test]
+  fn invalid_judgments_report_their_line() {
+    assert_eq!(parse_judgments("# comment\nvec\thttps://doc.rust-lang.org/std/vec/").unwrap_err(), "Line 2: expected `query <TAB> url <TAB> grade`");
+    assert_eq!(parse_judgments("vec\thttps://doc.rust-lang.org/std/vec/\thigh").unwrap_err(), "Line 1: invalid grade `high`");
+  }
+
+  fn grades() -> HashMap<String, u32> {
+    HashMap::from([("a".to_string(), 3), ("b".to_string(), 2), ("c".to_string(), 1), ("d".to_string(), 0)])
+  }
+
+  fn urls(urls: &[&str]) -> Vec<String> {
+    urls.iter().map(|url| url.to_string()).collect()
+  }
+
+  fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+  }
+
+  #[test]
+  fn ideal_rankings_score_one() {
+    let ranked = urls(&["a", "b", "c"]);
+
+    assert_close(ndcg(&ranked, &grades(), 10), 1.0);
+    assert_close(reciprocal_rank(&ranked, &grades(), 10), 1.0);
+    assert_close(recall(&ranked, &grades(), 10), 1.0);
+  }
+
+  #[test]
+  fn swapped_results_lose_gain_by_rank() {
+    // Gains 2^grade - 1 discounted by log2(rank + 1)
+    let ideal = 7.0 + 3.0 / 3f64.log2() + 1.0 / 4f64.log2();
+    assert_close(ndcg(&urls(&["b", "a", "c"]), &grades(), 10), (3.0 + 7.0 / 3f64.log2() + 1.0 / 4f64.log2()) / ideal);
+    assert_close(ndcg(&urls(&["b", "a"]), &grades(), 1), 3.0 / 7.0);
+  }
+
+  #[test]
+  fn unjudged_and_irrelevant_urls_count_as_not_relevant() {
+    let ranked = urls(&["unjudged", "d", "b"]);
+    let ideal = 7.0 + 3.0 / 3f64.log2() + 1.0 / 4f64.log2();
+
+    assert_close(ndcg(&ranked, &grades(), 10), (3.0 / 4f64.log2()) / ideal);
+    assert_close(reciprocal_rank(&ranked, &grades(), 10), 1.0 / 3.0);
+    assert_close(reciprocal_rank(&ranked, &grades(), 2), 0.0);
+    assert_close(recall(&ranked, &grades(), 10), 1.0 / 3.0);
+  }
+
+  #[test]
+  fn empty_results_and_queries_without_relevant_urls_score_zero() {
+    assert_close(ndcg(&[], &grades(), 10), 0.0);
+    assert_close(reciprocal_rank(&[], &grades(), 10), 0.0);
+    assert_close(recall(&[], &grades(), 10), 0.0);
+
+    let irrelevant = HashMap::from([("d".to_string(), 0)]);
+    assert_close(ndcg(&urls(&["d"]), &irrelevant, 10), 0.0);
+    assert_close(recall(&urls(&["d"]), &irrelevant, 10), 0.0);
+  }
+}
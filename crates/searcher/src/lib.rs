@@ -1,6 +1,36 @@
-use config::SITES;
+mod boosted_query;
+mod click_priors;
+mod collapse;
+mod diversify;
+mod error;
+mod evaluation;
+mod query_filters;
+mod ranking;
+mod result_cache;
+mod search_options;
+mod synonyms;
+mod versions;
+
+use std::{ops::Bound, path::Path, sync::{Arc, PoisonError, RwLock}, time::{SystemTime, UNIX_EPOCH}};
+
+use config::{COLLAPSE_CANDIDATES_FACTOR, DATA_DIR, NEAR_DUPLICATE_DISTANCE, PREFERRED_ECOSYSTEM_BOOST, SEARCH_CACHE_BYTES, SITES, STEMMED_FIELD_BOOST, SYNONYM_WEIGHT};
 use fuzzy_matcher::FuzzyMatcher;
-use tantivy::{aggregation::agg_result::AggregationResults, collector::{Count, MultiCollector, TopDocs}, query::{self, QueryParser}, schema::{Field, Schema}, Index, Score};
+use tantivy::{collector::{Count, MultiCollector, TopDocs}, query::{AllQuery, BooleanQuery, BoostQuery, EnableScoring, Explanation, Occur, PhraseQuery, Query, QueryParser, RangeQuery, TermQuery}, directory::error::OpenReadError, schema::{Facet, Field, IndexRecordOption, Schema, Type}, DateTime, DocAddress, DocSet, Index, IndexReader, ReloadPolicy, Score, Searcher, SegmentReader, TantivyError, Term};
+
+pub use click_priors::{normalize_query, ClickPriors};
+pub use error::SearchError;
+pub use evaluation::{ndcg, parse_judgments, recall, reciprocal_rank, Evaluation, Judgment, QueryEvaluation, EVALUATION_DEPTH};
+pub use query_filters::{extract_filters, QueryFilters};
+pub use result_cache::CacheStats;
+pub use tantivy::query::Explanation as ScoreExplanation;
+pub use search_options::{SearchOptions, SortOrder};
+pub use synonyms::{QueryExpansion, SynonymDictionary};
+pub use versions::compare_versions;
+
+use collapse::{collapse_duplicates, collapse_versions, Candidate, CollapsedCandidate};
+use diversify::{dedupe_urls, diversify};
+use ranking::Ranking;
+use result_cache::{cache_key, ResultCache};
 
 struct SearchFields {
   title: Field,
@@ -9,12 +39,24 @@ struct SearchFields {
   domain: Field,
   headings: Field,
   scraped_at: Field,
+  last_modified: Field,
+  lang: Field,
+  site: Field,
+  product: Field,
+  version: Field,
+  page_key: Field,
+  authority: Field,
+  simhash: Field,
 }
 
 pub struct DocSearcher {
   index: Index,
+  // Reloaded on every index commit, so a long-lived searcher sees newly crawled pages
+  reader: IndexReader,
   schema: Schema,
-  query_parser: QueryParser,
+  synonyms: SynonymDictionary,
+  click_priors: RwLock<ClickPriors>,
+  cache: ResultCache,
   fields: SearchFields
 }
 
@@ -26,6 +68,26 @@ pub struct SearchResult {
   pub heading: String,
   pub score: Score,
   pub scraped_at: i64,
+  pub last_modified: i64,
+  pub product: Option<String>,
+  pub version: Option<String>,
+  // Versions of the same page collapsed into this result
+  pub other_versions: Vec<String>,
+  // Urls of near-duplicate pages collapsed into this result
+  pub also_at: Vec<String>,
+  // Number of other hits from this result's site, shown as "more results from this site"
+  pub more_from_site: usize,
+  // How the score was computed, only with `SearchOptions::explain`
+  pub explanation: Option<Explanation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+  pub results: Vec<SearchResult>,
+  // Query terms which were expanded with synonyms, to explain why a result matched
+  pub expansions: Vec<QueryExpansion>,
+  // The query as it was parsed, only with `SearchOptions::explain`
+  pub parsed_query: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,9 +96,11 @@ pub struct GetCrawledUrlsResult {
   pub total: usize,
 }
 
-const CONTEXT_SIZE:usize = 100;
 const MAX_LENGTH:usize = 100;
 
+// Fields which can be searched, see `SearchOptions::field_boosts`
+pub const SEARCHABLE_FIELDS: [&str; 6] = ["title", "headings", "content", "anchors", "url", "domain"];
+
 fn generate_snippet(text: &str, query: &str) -> String {
   let matcher = fuzzy_matcher::skim::SkimMatcherV2::default();
 
@@ -49,23 +113,16 @@ fn generate_snippet(text: &str, query: &str) -> String {
     .max_by_key(|(_, score, _)| *score)
     .map(|(pos, _, indices)| (pos, indices));
 
-  if let Some(pos) = best_line {
+  if let Some((pos, indices)) = best_line {
     let lines: Vec<&str> = text.split_terminator("\n").collect();
-    let line = lines[pos.0];
+    let line = lines[pos];
 
-    if line.len() > MAX_LENGTH {
-      return format!("{}...", &line[..MAX_LENGTH]);
+    // `MAX_LENGTH` counts chars, like the match indices, so long lines are cut on a char boundary
+    match line.char_indices().nth(MAX_LENGTH) {
+      Some((end, _)) => format!("{}...", &line[..end]),
+      None if indices.is_empty() => format!("{}...", line),
+      None => format!("...{}...", line),
     }
-
-    let start_idx = pos.1.first();
-    if start_idx.is_none() {
-      return format!("{}...", &line[..MAX_LENGTH]);
-    }
-
-    let start = start_idx.unwrap().saturating_sub(CONTEXT_SIZE);
-    let end = (pos.0 + CONTEXT_SIZE).min(line.len());
-
-    return format!("...{}...", &line[start..end]);
   } else {
     // If no match found, return the beginning of the text
     let words: Vec<&str> = text.split_whitespace().take(20).collect();
@@ -79,137 +136,453 @@ fn generate_heading(text: &str, query: &str) -> String {
 
   let best_heading= text
     .split_terminator("\n")
-    .enumerate()
-    .filter_map(|(_, word)| {
+    .filter_map(|word| {
       matcher.fuzzy_match(word, query).map(|score| (word, score))
     })
     .max_by_key(|(_, score)| *score)
     .map(|(word, _)| word);
 
   if let Some(heading) = best_heading {
-    return heading.to_string();
+    heading.to_string()
   } else {
-    return "".to_string();
+    "".to_string()
   }
 }
 
 impl DocSearcher {
-  pub fn new(index_path: String) -> tantivy::Result<Self> {
-    let index = tantivy::Index::open_in_dir(index_path).unwrap();
+  // Searcher of the index at `index_path`, with the synonyms and click-through priors of `config::DATA_DIR`
+  pub fn new(index_path: String) -> Result<Self, SearchError> {
+    Self::with_data_dir(index_path, Path::new(DATA_DIR))
+  }
+
+  // Searcher of the index at `index_path`, with the synonyms and click-through priors of `data_dir`
+  pub fn with_data_dir(index_path: String, data_dir: &Path) -> Result<Self, SearchError> {
+    let index = schema::open_index(&index_path).map_err(|e| match e {
+      TantivyError::OpenDirectoryError(_) | TantivyError::OpenReadError(OpenReadError::FileDoesNotExist(_)) => SearchError::IndexNotFound(index_path.clone()),
+      e => SearchError::Index(e),
+    })?;
+    let reader = index.reader_builder().reload_policy(ReloadPolicy::OnCommit).try_into()?;
     let schema = index.schema();
+    let field = |name: &'static str| schema.get_field(name).ok_or(SearchError::MissingField(name));
 
     let fields = SearchFields {
-      title: schema.get_field("title").unwrap(),
-      content: schema.get_field("content").unwrap(),
-      url: schema.get_field("url").unwrap(),
-      domain: schema.get_field("domain").unwrap(),
-      headings: schema.get_field("headings").unwrap(),
-      scraped_at: schema.get_field("scraped_at").unwrap(),
+      title: field("title")?,
+      content: field("content")?,
+      url: field("url")?,
+      domain: field("domain")?,
+      headings: field("headings")?,
+      scraped_at: field("scraped_at")?,
+      last_modified: field("last_modified")?,
+      lang: field("lang")?,
+      site: field("site")?,
+      product: field("product")?,
+      version: field("version")?,
+      page_key: field("page_key")?,
+      authority: field("authority")?,
+      simhash: field("simhash")?,
     };
 
-    let mut query_parser = QueryParser::for_index(&index, vec![
-      fields.title,
-      fields.content,
-      fields.headings,
-    ]);
-
-    query_parser.set_field_boost(fields.title, 3.0);
-    query_parser.set_field_boost(fields.headings, 2.0);
-
     Ok(Self {
       index,
+      reader,
       schema,
-      query_parser,
+      synonyms: SynonymDictionary::from_config(data_dir),
+      click_priors: RwLock::new(ClickPriors::from_config(data_dir)),
+      cache: ResultCache::new(SEARCH_CACHE_BYTES),
       fields
     })
   }
 
-  pub fn search(&self, query_str: &str, limit: usize) -> tantivy::Result<Vec<SearchResult>> {
-    let reader = self.index.reader()?;
-    let searcher = reader.searcher();
+  // Replaces the click-through priors, dropping the results ranked with the previous ones
+  pub fn set_click_priors(&self, click_priors: ClickPriors) {
+    *self.click_priors.write().unwrap_or_else(PoisonError::into_inner) = click_priors;
+    self.cache.clear();
+  }
 
-    let query = self.query_parser.parse_query(query_str)?;
+  pub fn cache_stats(&self) -> CacheStats {
+    self.cache.stats()
+  }
 
-    let top_docs = TopDocs::with_limit(limit);
-    let mut multi_collector = MultiCollector::new();
-    let top_docs_handle = multi_collector.add_collector(top_docs);
+  pub fn num_docs(&self) -> u64 {
+    self.reader.searcher().num_docs()
+  }
 
-    let mut results = Vec::new();
+  // Query parser over the searched fields and their stemmed copies. Returns the exact-form fields
+  // with their boosts as well, for the phrase boost
+  fn query_parser(&self, options: &SearchOptions) -> Result<(QueryParser, Vec<(Field, Score)>), SearchError> {
+    let mut fields = Vec::new();
+    let mut boosts = Vec::new();
+    let mut exact_fields = Vec::new();
+    for (name, boost) in &options.field_boosts {
+      let field = Some(name)
+        .filter(|name| SEARCHABLE_FIELDS.contains(&name.as_str()))
+        .and_then(|name| self.schema.get_field(name))
+        .ok_or_else(|| SearchError::UnknownField(name.to_string()))?;
+      fields.push(field);
+      boosts.push((field, *boost));
+      exact_fields.push((field, *boost));
+
+      if let Some(stemmed_field) = self.schema.get_field(&format!("{}_stem", name)) {
+        fields.push(stemmed_field);
+        boosts.push((stemmed_field, boost * STEMMED_FIELD_BOOST));
+      }
+    }
+
+    let mut query_parser = QueryParser::for_index(&self.index, fields);
+    for (field, boost) in boosts {
+      query_parser.set_field_boost(field, boost);
+    }
+    if options.require_all_terms {
+      query_parser.set_conjunction_by_default();
+    }
+
+    Ok((query_parser, exact_fields))
+  }
+
+  // Matches pages containing all the terms of `text` within `phrase_slop` positions of each other
+  // in one of `fields`. `None` for single term queries
+  fn phrase_query(&self, text: &str, fields: &[(Field, Score)], options: &SearchOptions) -> Option<Box<dyn Query>> {
+    let clauses: Vec<(Occur, Box<dyn Query>)> = fields.iter()
+      .filter_map(|(field, boost)| {
+        let tokenizer = self.index.tokenizer_for_field(*field).ok()?;
+        let mut terms = Vec::new();
+        tokenizer.token_stream(text).process(&mut |token| terms.push(Term::from_field_text(*field, &token.text)));
+        if terms.len() < 2 {
+          return None;
+        }
+
+        let mut phrase_query = PhraseQuery::new(terms);
+        phrase_query.set_slop(options.phrase_slop);
+        Some((Occur::Should, Box::new(BoostQuery::new(Box::new(phrase_query), *boost)) as Box<dyn Query>))
+      })
+      .collect();
+
+    if clauses.is_empty() {
+      return None;
+    }
+    Some(Box::new(BoostQuery::new(Box::new(BooleanQuery::new(clauses)), options.phrase_boost)))
+  }
+
+  // Debug representation of the query tree with field names instead of field ids
+  fn describe_query(&self, query: &dyn Query) -> String {
+    self.schema.fields().fold(format!("{:?}", query), |description, (field, entry)| {
+      description
+        .replace(&format!("field={},", field.field_id()), &format!("field={},", entry.name()))
+        .replace(&format!("Field({})", field.field_id()), &format!("Field({})", entry.name()))
+    })
+  }
+
+  // Tantivy's explanation of the text match, with the ranking factors applied on top of it
+  fn explain(&self, searcher: &Searcher, query: &dyn Query, ranking: &Ranking, doc_address: DocAddress, score: Score, click_boost: Score) -> tantivy::Result<Explanation> {
+    let mut explanation = Explanation::new("Final score", score);
+    if let Some(text_match) = self.explain_query(searcher, query, doc_address)? {
+      explanation.add_detail(text_match);
+    }
+
+    let mut segment_ranking = ranking.for_segment(searcher.segment_reader(doc_address.segment_ord));
+    for factor in segment_ranking.explain(doc_address.doc_id) {
+      explanation.add_detail(factor);
+    }
+    if click_boost != 1.0 {
+      explanation.add_detail(Explanation::new("Click-through prior", click_boost));
+    }
+
+    Ok(explanation)
+  }
+
+  // Explains the score of a matching document, `None` when it doesn't match. Tantivy 0.19 explains
+  // every clause of a boolean query, seeking backwards in the postings of the clauses which only
+  // match later documents. Boolean clauses and boosted queries are explained one by one here, once
+  // their scorer matched, down to the terms and phrases which tantivy explains
+  fn explain_query(&self, searcher: &Searcher, query: &dyn Query, doc_address: DocAddress) -> tantivy::Result<Option<Explanation>> {
+    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+    let doc = doc_address.doc_id;
+    let weight = query.weight(EnableScoring::Enabled(searcher))?;
+    let mut scorer = weight.scorer(segment_reader, 1.0)?;
+    if scorer.doc() > doc || scorer.seek(doc) != doc {
+      return Ok(None);
+    }
+
+    if let Some(boolean_query) = query.downcast_ref::<BooleanQuery>() {
+      let mut explanation = Explanation::new("BooleanClause. Sum of ...", scorer.score());
+      for (occur, clause) in boolean_query.clauses() {
+        if *occur == Occur::MustNot {
+          continue;
+        }
+        if let Some(clause_explanation) = self.explain_query(searcher, clause.as_ref(), doc_address)? {
+          explanation.add_detail(clause_explanation);
+        }
+      }
+      return Ok(Some(explanation));
+    }
+
+    if let Some(boost_query) = query.downcast_ref::<BoostQuery>() {
+      let Some((boosted, boost)) = boosted_query::unboost(boost_query) else {
+        return Ok(Some(Explanation::new("Boosted clause, not explained further", scorer.score())));
+      };
+      let mut explanation = Explanation::new(format!("Boost x{} of ...", boost), scorer.score());
+      if let Some(boosted_explanation) = self.explain_query(searcher, boosted.as_ref(), doc_address)? {
+        explanation.add_detail(boosted_explanation);
+      }
+      return Ok(Some(explanation));
+    }
+
+    weight.explain(segment_reader, doc).map(Some)
+  }
+
+  // Results are cached until the next index commit. Explained searches are never cached, their
+  // explanations are large and only used for debugging
+  pub fn search(&self, query_str: &str, options: &SearchOptions) -> Result<SearchResults, SearchError> {
+    let searcher = self.reader.searcher();
+    if options.explain {
+      return self.search_uncached(&searcher, query_str, options);
+    }
+
+    let generation = searcher.generation().generation_id();
+    let key = cache_key(query_str, options);
+    if let Some(results) = self.cache.get(generation, &key) {
+      return Ok(results);
+    }
+
+    let results = self.search_uncached(&searcher, query_str, options)?;
+    self.cache.insert(generation, key, results.clone());
+    Ok(results)
+  }
+
+  fn search_uncached(&self, searcher: &Searcher, query_str: &str, options: &SearchOptions) -> Result<SearchResults, SearchError> {
+
+    let (text, filters) = extract_filters(query_str);
+    let lang = filters.lang.or_else(|| options.lang.clone());
+    let version = filters.version.or_else(|| options.version.clone());
 
-    let mut search_results = searcher.search(&query, &multi_collector)?;
-    let top_docs = top_docs_handle.extract(&mut search_results);
+    let (expanded_text, expansions) = if options.expand_synonyms {
+      self.synonyms.expand(&text, SYNONYM_WEIGHT)
+    } else {
+      (text.clone(), Vec::new())
+    };
+
+    let text_query: Box<dyn Query> = if text.trim().is_empty() {
+      Box::new(AllQuery)
+    } else {
+      let (query_parser, exact_fields) = self.query_parser(options)?;
+      let parsed_query = query_parser.parse_query(&expanded_text)?;
+
+      // Rank pages with the terms close together above pages where they are scattered
+      match self.phrase_query(&text, &exact_fields, options).filter(|_| options.phrase_boost > 0.0) {
+        Some(phrase_query) => Box::new(BooleanQuery::new(vec![(Occur::Must, parsed_query), (Occur::Should, phrase_query)])),
+        None => parsed_query,
+      }
+    };
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, text_query)];
+    if let Some(lang) = &lang {
+      let lang_term = Term::from_facet(self.fields.lang, &Facet::from_path(vec![lang]));
+      clauses.push((Occur::Must, Box::new(TermQuery::new(lang_term, IndexRecordOption::Basic))));
+    }
+    if let Some(version) = &version {
+      let version_term = Term::from_field_text(self.fields.version, version);
+      clauses.push((Occur::Must, Box::new(TermQuery::new(version_term, IndexRecordOption::Basic))));
+    }
+    if options.after.is_some() || options.before.is_some() {
+      let date_bound = |ts: Option<i64>, bound: fn(Term) -> Bound<Term>| match ts {
+        Some(ts) => bound(Term::from_field_date(self.fields.last_modified, DateTime::from_timestamp_millis(ts))),
+        None => Bound::Unbounded,
+      };
+      clauses.push((Occur::Must, Box::new(RangeQuery::new_term_bounds(
+        self.fields.last_modified,
+        Type::Date,
+        &date_bound(options.after, Bound::Included),
+        &date_bound(options.before, Bound::Excluded),
+      ))));
+    }
+
+    let query: Box<dyn Query> = if clauses.len() == 1 {
+      clauses.remove(0).1
+    } else {
+      Box::new(BooleanQuery::new(clauses))
+    };
+
+    // Boosting the preferred ecosystem is pointless when results are already filtered by one
+    let ranking = Arc::new(Ranking {
+      lang_field: self.fields.lang,
+      preferred_lang: options.preferred_lang.as_ref()
+        .filter(|_| lang.is_none())
+        .map(|lang| Facet::from_path(vec![lang])),
+      preferred_lang_boost: PREFERRED_ECOSYSTEM_BOOST,
+      last_modified_field: self.fields.last_modified,
+      freshness_weight: options.freshness_weight,
+      freshness_half_life_ms: options.freshness_half_life_days as f64 * 24.0 * 60.0 * 60.0 * 1000.0,
+      now_ms: SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_millis() as i64).unwrap_or_default(),
+      authority_field: self.fields.authority,
+      authority_weight: options.authority_weight,
+      site_field: self.fields.site,
+      site_boosts: options.site_boosts.iter()
+        .filter(|(_, boost)| **boost != 1.0)
+        .map(|(site, boost)| (Facet::from_path(vec![site]), *boost))
+        .collect(),
+    });
+
+    // Fetch more candidates than requested, so collapsing doesn't shrink the result list
+    let candidates_limit = if options.collapse_versions || options.collapse_duplicates || options.max_per_site.is_some() {
+      options.limit * COLLAPSE_CANDIDATES_FACTOR
+    } else {
+      options.limit
+    };
+
+    let top_docs: Vec<(Score, DocAddress)> = match options.sort {
+      SortOrder::Relevance => {
+        let ranking = ranking.clone();
+        let top_docs = TopDocs::with_limit(candidates_limit)
+          .tweak_score(move |segment_reader: &SegmentReader| ranking.segment_scorer(segment_reader));
+        searcher.search(query.as_ref(), &top_docs)?
+      },
+      SortOrder::Date => {
+        // Order by last modified date, keeping the relevance score for the response
+        let last_modified_field = self.fields.last_modified;
+        let ranking = ranking.clone();
+        let top_docs = TopDocs::with_limit(candidates_limit)
+          .tweak_score(move |segment_reader: &SegmentReader| {
+            let last_modified = segment_reader.fast_fields().date(last_modified_field).ok();
+            let mut segment_scorer = ranking.segment_scorer(segment_reader);
+
+            move |doc, score| {
+              let date = last_modified.as_ref().map(|dates| dates.get_val(doc).into_timestamp_millis()).unwrap_or_default();
+              (date, segment_scorer(doc, score))
+            }
+          });
+        searcher.search(query.as_ref(), &top_docs)?
+          .into_iter()
+          .map(|((_, score), doc_address)| (score, doc_address))
+          .collect()
+      },
+    };
 
+    let mut candidates = Vec::new();
     for (score, doc_address) in top_docs {
       let doc = searcher.doc(doc_address)?;
+      let text_value = |field: Field| doc.get_first(field).and_then(|f| f.as_text()).map(|f| f.to_string());
+
+      candidates.push(Candidate {
+        score,
+        doc_address,
+        url: text_value(self.fields.url).unwrap_or_default(),
+        domain: text_value(self.fields.domain).unwrap_or_default(),
+        page_key: text_value(self.fields.page_key),
+        version: text_value(self.fields.version),
+        simhash: doc.get_first(self.fields.simhash).and_then(|f| f.as_u64()),
+        click_boost: 1.0,
+        doc,
+      });
+    }
+
+    // Rerank the candidates by how often users clicked them, relative to their position
+    let click_priors = self.click_priors.read().unwrap_or_else(PoisonError::into_inner);
+    if options.click_weight > 0.0 && options.sort == SortOrder::Relevance && !click_priors.is_empty() {
+      for candidate in candidates.iter_mut() {
+        candidate.click_boost = click_priors.boost(query_str, &candidate.url).powf(options.click_weight);
+        candidate.score *= candidate.click_boost;
+      }
+      candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+
+    let candidates = dedupe_urls(candidates);
+    let mut collapsed = if options.collapse_versions {
+      collapse_versions(candidates)
+    } else {
+      candidates.into_iter()
+        .map(|candidate| CollapsedCandidate { candidate, other_versions: Vec::new(), also_at: Vec::new(), more_from_site: 0 })
+        .collect()
+    };
+    if options.collapse_duplicates {
+      collapsed = collapse_duplicates(collapsed, NEAR_DUPLICATE_DISTANCE);
+    }
+    if let Some(max_per_site) = options.max_per_site {
+      collapsed = diversify(collapsed, max_per_site, options.limit);
+    }
+
+    let mut results = Vec::new();
+    for CollapsedCandidate { candidate, other_versions, also_at, more_from_site } in collapsed.into_iter().take(options.limit) {
+      let Candidate { score, doc_address, doc, url, version, click_boost, .. } = candidate;
+      let explanation = if options.explain {
+        Some(self.explain(searcher, query.as_ref(), &ranking, doc_address, score, click_boost)?)
+      } else {
+        None
+      };
 
       let result = SearchResult {
         title: doc.get_first(self.fields.title)
         .and_then(|f| f.as_text())
         .unwrap_or_default()
         .to_string(),
-        url: doc.get_first(self.fields.url)
-        .and_then(|f| f.as_text())
-        .unwrap_or_default()
-        .to_string(),
+        url,
         content_snippet: generate_snippet(
           doc.get_first(self.fields.content)
           .and_then(|f| f.as_text())
           .unwrap_or_default(),
-          query_str,
+          &text,
         ),
         heading: generate_heading(
           doc.get_first(self.fields.headings)
           .and_then(|f| f.as_text())
           .unwrap_or_default(),
-          query_str,
+          &text,
         ),
-        scraped_at: doc.get_first(self.fields.scraped_at).and_then(|f| f.as_date()).unwrap().into_timestamp_millis(),
+        scraped_at: doc.get_first(self.fields.scraped_at).and_then(|f| f.as_date()).map(|d| d.into_timestamp_millis()).unwrap_or_default(),
+        last_modified: doc.get_first(self.fields.last_modified).and_then(|f| f.as_date()).map(|d| d.into_timestamp_millis()).unwrap_or_default(),
+        product: doc.get_first(self.fields.product).and_then(|f| f.as_text()).map(|f| f.to_string()),
+        version,
+        other_versions,
+        also_at,
+        more_from_site,
+        explanation,
         score,
       };
 
       results.push(result);
     };
 
-    Ok(results)
+    Ok(SearchResults {
+      results,
+      expansions,
+      parsed_query: options.explain.then(|| self.describe_query(query.as_ref())),
+    })
   }
 
   // IMPROV: Use facets
-  pub fn get_pages_per_site(&self) -> tantivy::Result<Vec<(&str, usize)>> {
-    let reader = self.index.reader()?;
-    let searcher = reader.searcher();
+  pub fn get_pages_per_site(&self) -> Result<Vec<(&str, usize)>, SearchError> {
+    let searcher = self.reader.searcher();
     let query_parser = QueryParser::for_index(
       &self.index,
-      vec![self.schema.get_field("domain").unwrap()]
+      vec![self.fields.domain]
     );
 
     let mut result = Vec::new();
     for site in SITES {
-      let query = query_parser.parse_query(site).unwrap();
+      let query = query_parser.parse_query(site)?;
       let count = searcher.search(&query, &Count)?;
 
       result.push((site, count));
     }
 
-    return Ok(result);
+    Ok(result)
   }
 
-  pub fn get_crawled_urls(&self, domain: Option<String>, limit: usize, offset: usize) -> tantivy::Result<GetCrawledUrlsResult> {
-    let reader = self.index.reader()?;
-    let searcher = reader.searcher();
+  pub fn get_crawled_urls(&self, domain: Option<String>, limit: usize, offset: usize) -> Result<GetCrawledUrlsResult, SearchError> {
+    let searcher = self.reader.searcher();
     let query_parser = QueryParser::for_index(
       &self.index,
-      vec![self.schema.get_field("domain").unwrap()]
+      vec![self.fields.domain]
     );
 
     let query_string = domain.unwrap_or_else(|| {"*".to_string()});
-    let query = query_parser.parse_query(&query_string).unwrap();
+    let query = query_parser.parse_query(&query_string)?;
 
     let top_docs_collector = TopDocs::with_limit(limit).and_offset(offset);
     let mut multi_collector = MultiCollector::new();
     let top_docs_handle = multi_collector.add_collector(top_docs_collector);
     let count_handle = multi_collector.add_collector(Count);
 
-    let mut multifruits= searcher.search(&query, &multi_collector).unwrap();
+    let mut multifruits= searcher.search(&query, &multi_collector)?;
     let top_docs = top_docs_handle.extract(&mut multifruits);
     let count = count_handle.extract(&mut multifruits);
 
@@ -227,4 +600,101 @@ impl DocSearcher {
       total: count,
     })
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use tantivy::Document;
+
+  use super::*;
+
+  #[test]
+  fn snippets_of_long_lines_are_cut_on_a_char_boundary() {
+    // Byte 100 is in the middle of the dash
+    let line = format!("{}— don’t communicate by sharing memory, share memory with goroutines’ channels", "Go ".repeat(33));
+    assert!(!line.is_char_boundary(MAX_LENGTH));
+    let snippet = generate_snippet(&format!("Intro\n{}", line), "goroutines");
+
+    assert_eq!(snippet.chars().count(), MAX_LENGTH + "...".len());
+    assert!(line.starts_with(snippet.trim_end_matches("...")));
+  }
+
+  #[test]
+  fn short_lines_with_multibyte_chars_are_kept_whole() {
+    let line = format!("{} goroutines", "é".repeat(60));
+    assert!(line.len() > MAX_LENGTH);
+
+    assert_eq!(generate_snippet(&line, "goroutines"), format!("...{}...", line));
+  }
+
+  // Searcher over an index of `(url, title, content)` pages
+  fn searcher(dir: &std::path::Path, pages: &[(&str, &str, &str)]) -> DocSearcher {
+    let index = schema::open_or_create_index(dir).unwrap();
+    let schema = index.schema();
+    let field = |name| schema.get_field(name).unwrap();
+    let mut writer = index.writer(15_000_000).unwrap();
+    for (url, title, content) in pages {
+      let mut doc = Document::default();
+      doc.add_text(field("url"), url);
+      doc.add_bytes(field("url_id"), url.as_bytes());
+      doc.add_text(field("domain"), url.split('/').nth(2).unwrap());
+      doc.add_text(field("title"), title);
+      doc.add_text(field("content"), content);
+      doc.add_date(field("last_modified"), DateTime::from_timestamp_millis(0));
+      schema::add_derived_fields(&index, &mut doc);
+      writer.add_document(doc).unwrap();
+    }
+    writer.commit().unwrap();
+
+    DocSearcher::new(dir.to_string_lossy().into_owned()).unwrap()
+  }
+
+  fn details(explanation: &Explanation) -> String {
+    explanation.to_pretty_json()
+  }
+
+  // Checks the clauses of every boolean node add up to its value, and boost nodes multiply their clause's
+  fn assert_adds_up(node: &serde_json::Value) {
+    let value = node["value"].as_f64().unwrap();
+    let description = node["description"].as_str().unwrap();
+    let details = node["details"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let detail = |i: usize| details[i]["value"].as_f64().unwrap();
+
+    let expected = if description.starts_with("BooleanClause") {
+      Some((0..details.len()).map(detail).sum::<f64>())
+    } else if let Some(boost) = description.strip_prefix("Boost x").and_then(|boost| boost.strip_suffix(" of ...")) {
+      assert_eq!(details.len(), 1, "{}", node);
+      Some(boost.parse::<f64>().unwrap() * detail(0))
+    } else {
+      assert!(!description.contains("not explained"), "{}", node);
+      None
+    };
+    if let Some(expected) = expected {
+      assert!((value - expected).abs() <= 1e-4 * value.max(1.0), "{} instead of {} in {}", value, expected, node);
+    }
+    details.iter().for_each(assert_adds_up);
+  }
+
+  #[test]
+  fn explains_results_which_only_match_some_clauses() {
+    let dir = tempfile::tempdir().unwrap();
+    let searcher = searcher(dir.path(), &[
+      ("https://doc.rust-lang.org/std/vec/", "Vec", "A contiguous growable array type"),
+      ("https://doc.rust-lang.org/std/string/", "String", "A growable UTF-8 encoded string"),
+      ("https://go.dev/doc/slices", "Slices", "Growable array slices in Go"),
+    ]);
+    let options = SearchOptions { explain: true, max_per_site: None, ..SearchOptions::default() };
+
+    // Boosted clauses, phrases and the phrase boost skip some of the pages
+    for query in ["string growable array", "growable array", "\"growable array\" OR utf^2", "(utf contiguous slices)^2"] {
+      let results = searcher.search(query, &options).unwrap();
+      assert_eq!(results.results.len(), 3, "{}", query);
+      for result in &results.results {
+        let explanation = result.explanation.as_ref().unwrap();
+        assert_eq!(explanation.value(), result.score);
+        assert!(details(explanation).contains("TermQuery, product of"), "{}", details(explanation));
+        assert_adds_up(&serde_json::from_str(&details(explanation)).unwrap());
+      }
+    }
+  }
+}
@@ -0,0 +1,27 @@
+// Filters which can be written inline in the query string, e.g. `lang:rust async`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryFilters {
+  pub lang: Option<String>,
+  pub version: Option<String>,
+}
+
+// Splits the supported `key:value` filters out of the raw query string.
+// Returns the remaining free text ( passed on to the query parser ) and the filters
+pub fn extract_filters(query_str: &str) -> (String, QueryFilters) {
+  let mut filters = QueryFilters::default();
+  let mut terms = Vec::new();
+
+  for token in query_str.split_whitespace() {
+    match token.split_once(':') {
+      Some(("lang", value)) if !value.is_empty() => {
+        filters.lang = Some(value.to_lowercase());
+      },
+      Some(("version", value)) if !value.is_empty() => {
+        filters.version = Some(value.to_string());
+      },
+      _ => terms.push(token),
+    }
+  }
+
+  (terms.join(" "), filters)
+}
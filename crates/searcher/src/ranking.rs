@@ -0,0 +1,148 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tantivy::{fastfield::{Column, FacetReader}, query::Explanation, schema::{Facet, Field}, DateTime, DocId, Score, SegmentReader};
+
+// Query independent adjustments applied on top of the BM25 score
+pub struct Ranking {
+  pub lang_field: Field,
+  pub preferred_lang: Option<Facet>,
+  pub preferred_lang_boost: Score,
+  pub last_modified_field: Field,
+  // Weight ( 0 - 1 ) of the freshness decay, the rest of the score is left to relevance
+  pub freshness_weight: Score,
+  pub freshness_half_life_ms: f64,
+  pub now_ms: i64,
+  pub authority_field: Field,
+  // Weight of the page's authority, a page with authority 1 scores `1 + weight` times higher
+  pub authority_weight: Score,
+  pub site_field: Field,
+  // Score multiplier per site facet, sites which aren't listed keep their score
+  pub site_boosts: Vec<(Facet, Score)>,
+}
+
+// The ranking signals of a single segment. Every factor is a multiplier of the score, 1 when the
+// signal is disabled or doesn't apply to the document
+pub struct SegmentRanking {
+  preferred_lang: Option<(FacetReader, u64)>,
+  preferred_lang_boost: Score,
+  last_modified: Option<Arc<dyn Column<DateTime>>>,
+  freshness_weight: Score,
+  freshness_half_life_ms: f64,
+  now_ms: i64,
+  authority: Option<Arc<dyn Column<f64>>>,
+  authority_weight: Score,
+  site_boosts: Option<(FacetReader, HashMap<u64, Score>)>,
+  ords: Vec<u64>,
+}
+
+impl Ranking {
+  pub fn for_segment(&self, segment_reader: &SegmentReader) -> SegmentRanking {
+    // Facet ordinals are segment local, so look up the preferred ecosystem and boosted sites once per segment
+    let preferred_lang = self.preferred_lang.as_ref().and_then(|facet| {
+      let facet_reader = segment_reader.facet_reader(self.lang_field).ok()?;
+      let ord = facet_reader.facet_dict().term_ord(facet.encoded_str()).ok()??;
+      Some((facet_reader, ord))
+    });
+
+    let last_modified = if self.freshness_weight > 0.0 {
+      segment_reader.fast_fields().date(self.last_modified_field).ok()
+    } else {
+      None
+    };
+
+    let authority = if self.authority_weight > 0.0 {
+      segment_reader.fast_fields().f64(self.authority_field).ok()
+    } else {
+      None
+    };
+
+    let site_boosts = segment_reader.facet_reader(self.site_field).ok()
+      .map(|facet_reader| {
+        let boost_by_ord: HashMap<u64, Score> = self.site_boosts.iter()
+          .filter_map(|(facet, boost)| {
+            let ord = facet_reader.facet_dict().term_ord(facet.encoded_str()).ok()??;
+            Some((ord, *boost))
+          })
+          .collect();
+        (facet_reader, boost_by_ord)
+      })
+      .filter(|(_, boost_by_ord)| !boost_by_ord.is_empty());
+
+    SegmentRanking {
+      preferred_lang,
+      preferred_lang_boost: self.preferred_lang_boost,
+      last_modified,
+      freshness_weight: self.freshness_weight.min(1.0),
+      freshness_half_life_ms: self.freshness_half_life_ms,
+      now_ms: self.now_ms,
+      authority,
+      authority_weight: self.authority_weight,
+      site_boosts,
+      ords: Vec::new(),
+    }
+  }
+
+  // Builds the scoring function for a single segment. Used with `TopDocs::tweak_score`
+  pub fn segment_scorer(&self, segment_reader: &SegmentReader) -> impl FnMut(DocId, Score) -> Score + 'static {
+    let mut segment_ranking = self.for_segment(segment_reader);
+    move |doc, score| score * segment_ranking.multiplier(doc)
+  }
+}
+
+impl SegmentRanking {
+  pub fn ecosystem_boost(&mut self, doc: DocId) -> Score {
+    let Some((facet_reader, preferred_ord)) = &self.preferred_lang else {
+      return 1.0;
+    };
+
+    self.ords.clear();
+    facet_reader.facet_ords(doc, &mut self.ords);
+    if self.ords.contains(preferred_ord) { self.preferred_lang_boost } else { 1.0 }
+  }
+
+  // Exponential decay over the page's age: 1 for a page modified now, 0.5 after one half life
+  pub fn freshness(&self, doc: DocId) -> Score {
+    let Some(last_modified) = &self.last_modified else {
+      return 1.0;
+    };
+
+    let age_ms = (self.now_ms - last_modified.get_val(doc).into_timestamp_millis()).max(0) as f64;
+    let decay = 0.5f64.powf(age_ms / self.freshness_half_life_ms) as Score;
+    (1.0 - self.freshness_weight) + self.freshness_weight * decay
+  }
+
+  pub fn authority(&self, doc: DocId) -> Score {
+    match &self.authority {
+      Some(authority) => 1.0 + self.authority_weight * authority.get_val(doc) as Score,
+      None => 1.0,
+    }
+  }
+
+  pub fn site_boost(&mut self, doc: DocId) -> Score {
+    let Some((facet_reader, boost_by_ord)) = &self.site_boosts else {
+      return 1.0;
+    };
+
+    self.ords.clear();
+    facet_reader.facet_ords(doc, &mut self.ords);
+    self.ords.iter().find_map(|ord| boost_by_ord.get(ord)).copied().unwrap_or(1.0)
+  }
+
+  pub fn multiplier(&mut self, doc: DocId) -> Score {
+    self.ecosystem_boost(doc) * self.freshness(doc) * self.authority(doc) * self.site_boost(doc)
+  }
+
+  // The factors which changed the document's score, as children of an explanation
+  pub fn explain(&mut self, doc: DocId) -> Vec<Explanation> {
+    [
+      ("Preferred ecosystem boost", self.ecosystem_boost(doc)),
+      ("Freshness", self.freshness(doc)),
+      ("Authority", self.authority(doc)),
+      ("Site boost", self.site_boost(doc)),
+    ]
+      .into_iter()
+      .filter(|(_, factor)| *factor != 1.0)
+      .map(|(name, factor)| Explanation::new(name, factor))
+      .collect()
+  }
+}
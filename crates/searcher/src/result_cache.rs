@@ -0,0 +1,225 @@
+use std::{mem::size_of, sync::{Mutex, MutexGuard, PoisonError}};
+
+use lru::LruCache;
+
+use crate::{SearchOptions, SearchResult, SearchResults};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  pub entries: usize,
+  pub bytes: usize,
+  pub capacity_bytes: usize,
+}
+
+struct CacheState {
+  entries: LruCache<String, (SearchResults, usize)>,
+  bytes: usize,
+  // Reader generation the cached results were computed on
+  generation: u64,
+  hits: u64,
+  misses: u64,
+}
+
+impl CacheState {
+  // Drops the cached results when the reader moved to a newer generation ( the index was committed ).
+  // Returns false for searches on an older generation, whose results must not be cached
+  fn sync_generation(&mut self, generation: u64) -> bool {
+    if generation > self.generation {
+      self.entries.clear();
+      self.bytes = 0;
+      self.generation = generation;
+    }
+    generation == self.generation
+  }
+}
+
+// Least recently used search results, bounded by their approximate size in memory
+pub struct ResultCache {
+  capacity_bytes: usize,
+  state: Mutex<CacheState>,
+}
+
+// Searches differing only in spacing are the same search. Case is kept, `AND` / `OR` are operators
+pub fn cache_key(query: &str, options: &SearchOptions) -> String {
+  format!("{}\n{:?}", query.split_whitespace().collect::<Vec<_>>().join(" "), options)
+}
+
+fn approximate_size(key: &str, results: &SearchResults) -> usize {
+  let strings_size = |strings: &[String]| strings.iter().map(|s| size_of::<String>() + s.len()).sum::<usize>();
+
+  let results_size: usize = results.results.iter()
+    .map(|result| {
+      size_of::<SearchResult>()
+        + result.title.len()
+        + result.url.len()
+        + result.content_snippet.len()
+        + result.heading.len()
+        + result.product.as_ref().map_or(0, |product| product.len())
+        + result.version.as_ref().map_or(0, |version| version.len())
+        + strings_size(&result.other_versions)
+        + strings_size(&result.also_at)
+    })
+    .sum();
+  let expansions_size: usize = results.expansions.iter()
+    .map(|expansion| expansion.term.len() + strings_size(&expansion.alternatives))
+    .sum();
+
+  key.len() + size_of::<SearchResults>() + results_size + expansions_size
+}
+
+impl ResultCache {
+  pub fn new(capacity_bytes: usize) -> Self {
+    Self {
+      capacity_bytes,
+      state: Mutex::new(CacheState {
+        entries: LruCache::unbounded(),
+        bytes: 0,
+        generation: 0,
+        hits: 0,
+        misses: 0,
+      }),
+    }
+  }
+
+  // A panic while holding the lock leaves the cache consistent, sizes are updated with the entries
+  fn state(&self) -> MutexGuard<'_, CacheState> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  pub fn get(&self, generation: u64, key: &str) -> Option<SearchResults> {
+    let mut state = self.state();
+    let cached = if state.sync_generation(generation) {
+      state.entries.get(key).map(|(results, _)| results.clone())
+    } else {
+      None
+    };
+
+    match cached {
+      Some(_) => state.hits += 1,
+      None => state.misses += 1,
+    }
+    cached
+  }
+
+  pub fn insert(&self, generation: u64, key: String, results: SearchResults) {
+    let size = approximate_size(&key, &results);
+    if size > self.capacity_bytes {
+      return;
+    }
+
+    let mut state = self.state();
+    if !state.sync_generation(generation) {
+      return;
+    }
+    if let Some((_, replaced_size)) = state.entries.put(key, (results, size)) {
+      state.bytes -= replaced_size;
+    }
+    state.bytes += size;
+
+    while state.bytes > self.capacity_bytes {
+      match state.entries.pop_lru() {
+        Some((_, (_, evicted_size))) => state.bytes -= evicted_size,
+        None => break,
+      }
+    }
+  }
+
+  // Drops all cached results, for changes to the ranking which don't go through an index commit
+  pub fn clear(&self) {
+    let mut state = self.state();
+    state.entries.clear();
+    state.bytes = 0;
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    let state = self.state();
+    CacheStats {
+      hits: state.hits,
+      misses: state.misses,
+      entries: state.entries.len(),
+      bytes: state.bytes,
+      capacity_bytes: self.capacity_bytes,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn results(url: &str) -> SearchResults {
+    let result = SearchResult {
+      title: "Vec in std::vec".to_string(),
+      url: url.to_string(),
+      content_snippet: "A contiguous growable array type...".to_string(),
+      heading: String::new(),
+      score: 1.0,
+      scraped_at: 0,
+      last_modified: 0,
+      product: None,
+      version: None,
+      other_versions: Vec::new(),
+      also_at: Vec::new(),
+      more_from_site: 0,
+      explanation: None,
+    };
+    SearchResults { results: vec![result], expansions: Vec::new(), parsed_query: None }
+  }
+
+  fn cached_urls(cache: &ResultCache, generation: u64, key: &str) -> Option<Vec<String>> {
+    cache.get(generation, key).map(|results| results.results.into_iter().map(|result| result.url).collect())
+  }
+
+  #[test]
+  fn keys_ignore_spacing_but_not_case_or_options() {
+    let options = SearchOptions::default();
+    assert_eq!(cache_key(" rust   vec ", &options), cache_key("rust vec", &options));
+    assert_ne!(cache_key("rust AND vec", &options), cache_key("rust and vec", &options));
+    assert_ne!(cache_key("rust", &options), cache_key("rust", &SearchOptions { limit: 20, ..SearchOptions::default() }));
+  }
+
+  #[test]
+  fn newer_generations_drop_the_cached_results() {
+    let cache = ResultCache::new(1 << 20);
+    cache.insert(1, "vec".to_string(), results("https://doc.rust-lang.org/std/vec/"));
+    assert_eq!(cached_urls(&cache, 1, "vec").unwrap(), ["https://doc.rust-lang.org/std/vec/"]);
+
+    assert_eq!(cached_urls(&cache, 2, "vec"), None);
+    // Searches still running on the previous generation don't cache stale results
+    cache.insert(1, "vec".to_string(), results("https://doc.rust-lang.org/std/vec/"));
+    assert_eq!(cached_urls(&cache, 2, "vec"), None);
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries, stats.bytes), (1, 2, 0, 0));
+  }
+
+  #[test]
+  fn least_recently_used_results_are_evicted_over_capacity() {
+    let size = approximate_size("a", &results("https://a.com/"));
+    let cache = ResultCache::new(size * 2);
+    cache.insert(1, "a".to_string(), results("https://a.com/"));
+    cache.insert(1, "b".to_string(), results("https://b.com/"));
+    assert!(cache.get(1, "a").is_some());
+
+    cache.insert(1, "c".to_string(), results("https://c.com/"));
+    assert!(cache.get(1, "a").is_some());
+    assert!(cache.get(1, "b").is_none());
+    assert!(cache.get(1, "c").is_some());
+    assert_eq!(cache.stats().bytes, size * 2);
+
+    // Replacing an entry doesn't count its size twice
+    cache.insert(1, "c".to_string(), results("https://c.com/"));
+    assert_eq!((cache.stats().entries, cache.stats().bytes), (2, size * 2));
+  }
+
+  #[test]
+  fn results_larger_than_the_capacity_are_not_cached() {
+    let cache = ResultCache::new(16);
+    cache.insert(1, "a".to_string(), results("https://a.com/"));
+
+    assert!(cache.get(1, "a").is_none());
+    assert_eq!(cache.stats().bytes, 0);
+  }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use config::{AUTHORITY_WEIGHT, CLICK_WEIGHT, FIELD_BOOSTS, FRESHNESS_HALF_LIFE_DAYS, FRESHNESS_WEIGHT, MAX_RESULTS_PER_SITE, MAX_SEARCH_LIMIT, PHRASE_BOOST, PHRASE_SLOP, PREFERRED_ECOSYSTEM, REQUIRE_ALL_TERMS, SITE_BOOSTS};
+use tantivy::Score;
+
+use crate::SEARCHABLE_FIELDS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+  #[default]
+  Relevance,
+  // Most recently modified first
+  Date,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+  pub limit: usize,
+  // Searched fields and their boosts, see `SEARCHABLE_FIELDS`. Stemmed copies are searched along with their field
+  pub field_boosts: Vec<(String, Score)>,
+  // Only match pages containing all the query terms instead of any of them
+  pub require_all_terms: bool,
+  // Boost of pages containing the query terms within `phrase_slop` positions of each other, 0 disables
+  pub phrase_boost: Score,
+  pub phrase_slop: u32,
+  // Only return pages from this ecosystem ( same as `lang:<ecosystem>` in the query )
+  pub lang: Option<String>,
+  // Boost pages from this ecosystem without filtering out the rest
+  pub preferred_lang: Option<String>,
+  // Only return pages of this documentation version ( same as `version:<version>` in the query )
+  pub version: Option<String>,
+  // Show a single result per page across documentation versions, preferring the latest version
+  pub collapse_versions: bool,
+  // Show a single result for near-duplicate pages ( mirrors, print views ), listing the others in `also_at`
+  pub collapse_duplicates: bool,
+  // Show at most this many results per site before the other sites' results, `None` disables the cap
+  pub max_per_site: Option<usize>,
+  // Expand abbreviations and synonyms in the query, see `SynonymDictionary`
+  pub expand_synonyms: bool,
+  pub sort: SortOrder,
+  // Only return pages last modified at or after / before this time, in epoch milliseconds
+  pub after: Option<i64>,
+  pub before: Option<i64>,
+  // Weight ( 0 - 1 ) of the last modified date in the score, 0 disables freshness ranking
+  pub freshness_weight: Score,
+  pub freshness_half_life_days: f32,
+  // Weight of the page's authority ( PageRank within its site ) in the score, 0 disables it
+  pub authority_weight: Score,
+  // Score multiplier per site ( e.g. `doc.rust-lang.org` ), defaults to `config::SITE_BOOSTS`
+  pub site_boosts: BTreeMap<String, Score>,
+  // Weight ( 0 - 1 ) of the click-through priors, see `ClickPriors`. 0 disables them
+  pub click_weight: Score,
+  // Return how the score of every result was computed and the parsed query, for debugging rankings
+  pub explain: bool,
+}
+
+impl Default for SearchOptions {
+  fn default() -> Self {
+    Self {
+      limit: 10,
+      field_boosts: FIELD_BOOSTS.iter().map(|(field, boost)| (field.to_string(), *boost)).collect(),
+      require_all_terms: REQUIRE_ALL_TERMS,
+      phrase_boost: PHRASE_BOOST,
+      phrase_slop: PHRASE_SLOP,
+      lang: None,
+      preferred_lang: PREFERRED_ECOSYSTEM.map(|lang| lang.to_string()),
+      version: None,
+      collapse_versions: true,
+      collapse_duplicates: true,
+      max_per_site: Some(MAX_RESULTS_PER_SITE).filter(|max| *max > 0),
+      expand_synonyms: true,
+      sort: SortOrder::Relevance,
+      after: None,
+      before: None,
+      freshness_weight: FRESHNESS_WEIGHT,
+      freshness_half_life_days: FRESHNESS_HALF_LIFE_DAYS,
+      authority_weight: AUTHORITY_WEIGHT,
+      site_boosts: SITE_BOOSTS.iter().map(|(site, boost)| (site.to_string(), *boost)).collect(),
+      click_weight: CLICK_WEIGHT,
+      explain: false,
+    }
+  }
+}
+
+// Parses `name:boost` pairs separated by commas, e.g. `title:5,anchors:1`
+fn parse_boosts(value: &str) -> Result<Vec<(String, Score)>, String> {
+  value.split(',')
+    .filter(|pair| !pair.trim().is_empty())
+    .map(|pair| {
+      let (name, boost) = pair.rsplit_once(':').ok_or_else(|| format!("Expected name:boost, got `{}`", pair))?;
+      let boost = boost.trim().parse::<Score>().ok()
+        .filter(|boost| *boost >= 0.0)
+        .ok_or_else(|| format!("Invalid boost `{}` for {}", boost, name))?;
+      Ok((name.trim().to_lowercase(), boost))
+    })
+    .collect()
+}
+
+impl SearchOptions {
+  // Ranking options from a tuning file, applied over the defaults. One `option = value` entry per
+  // line, lines starting with `#` are comments. Options are named like the search API parameters
+  pub fn from_overrides(contents: &str) -> Result<Self, String> {
+    let mut options = Self::default();
+    for line in contents.lines().map(str::trim).filter(|line| !line.starts_with('#')) {
+      let Some((name, value)) = line.split_once('=') else {
+        continue;
+      };
+      options.apply_override(name.trim(), value.trim())?;
+    }
+
+    Ok(options)
+  }
+
+  pub fn apply_override(&mut self, name: &str, value: &str) -> Result<(), String> {
+    fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+      value.parse().map_err(|_| format!("Invalid `{}`: {}", name, value))
+    }
+
+    match name {
+      "limit" => self.limit = parse(name, value)?,
+      "fields" => {
+        self.field_boosts = value.split(',')
+          .map(|field| field.trim().to_lowercase())
+          .filter(|field| !field.is_empty())
+          .map(|field| {
+            let boost = self.field_boosts.iter().find(|(name, _)| *name == field).map(|(_, boost)| *boost).unwrap_or(1.0);
+            (field, boost)
+          })
+          .collect();
+      },
+      "boost" => {
+        for (field, boost) in parse_boosts(value)? {
+          match self.field_boosts.iter_mut().find(|(name, _)| *name == field) {
+            Some(field_boost) => field_boost.1 = boost,
+            None => self.field_boosts.push((field, boost)),
+          }
+        }
+      },
+      "operator" => self.require_all_terms = match value.to_lowercase().as_str() {
+        "or" => false,
+        "and" => true,
+        _ => return Err(format!("Invalid `operator`: {}", value)),
+      },
+      "phrase_boost" => self.phrase_boost = parse(name, value)?,
+      "slop" => self.phrase_slop = parse(name, value)?,
+      "prefer" => self.preferred_lang = Some(value.to_lowercase()).filter(|lang| !lang.is_empty()),
+      "synonyms" => self.expand_synonyms = parse(name, value)?,
+      "collapse_versions" => self.collapse_versions = parse(name, value)?,
+      "collapse_duplicates" => self.collapse_duplicates = parse(name, value)?,
+      "max_per_site" => self.max_per_site = Some(parse(name, value)?).filter(|max| *max > 0),
+      "freshness" => self.freshness_weight = parse(name, value)?,
+      "half_life_days" => self.freshness_half_life_days = parse(name, value)?,
+      "authority" => self.authority_weight = parse(name, value)?,
+      "site_boost" => self.site_boosts.extend(parse_boosts(value)?),
+      "clicks" => self.click_weight = parse(name, value)?,
+      _ => return Err(format!("Unknown option `{}`", name)),
+    }
+
+    self.validate()
+  }
+
+  // Checks the options are in range, whether they were set by `apply_override` or directly. Weights
+  // which aren't numbers ( NaN ) are rejected as well
+  pub fn validate(&self) -> Result<(), String> {
+    if !(1..=MAX_SEARCH_LIMIT).contains(&self.limit) {
+      return Err(format!("`limit` must be between 1 and {}", MAX_SEARCH_LIMIT));
+    }
+    if let Some((field, _)) = self.field_boosts.iter().find(|(field, _)| !SEARCHABLE_FIELDS.contains(&field.as_str())) {
+      return Err(format!("Unknown field `{}`, expected one of {}", field, SEARCHABLE_FIELDS.join(", ")));
+    }
+    if self.field_boosts.is_empty() {
+      return Err("`fields` cannot be empty".to_string());
+    }
+    if let Some((field, _)) = self.field_boosts.iter().find(|(_, boost)| !(0.0..).contains(boost)) {
+      return Err(format!("Boost of `{}` must not be negative", field));
+    }
+    if let Some((site, _)) = self.site_boosts.iter().find(|(_, boost)| !(0.0..).contains(*boost)) {
+      return Err(format!("Boost of `{}` must not be negative", site));
+    }
+    if !(0.0..).contains(&self.phrase_boost) {
+      return Err("`phrase_boost` must not be negative".to_string());
+    }
+    if !(0.0..=1.0).contains(&self.freshness_weight) {
+      return Err("`freshness` must be between 0 and 1".to_string());
+    }
+    if self.freshness_half_life_days.is_nan() || self.freshness_half_life_days <= 0.0 {
+      return Err("`half_life_days` must be positive".to_string());
+    }
+    if !(0.0..).contains(&self.authority_weight) {
+      return Err("`authority` must not be negative".to_string());
+    }
+    if !(0.0..=1.0).contains(&self.click_weight) {
+      return Err("`clicks` must be between 0 and 1".to_string());
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn overrides_skip_comment_lines_only() {
+    let options = SearchOptions::from_overrides("# Tuned on evaluation/judgments.tsv\n  # slop = 9\nslop = 2\nprefer = C#\n\nsynonyms = false\n").unwrap();
+
+    assert_eq!(options.phrase_slop, 2);
+    assert_eq!(options.preferred_lang.as_deref(), Some("c#"));
+    assert!(!options.expand_synonyms);
+  }
+
+  #[test]
+  fn overrides_reject_unknown_options() {
+    assert_eq!(SearchOptions::from_overrides("stemming = true").unwrap_err(), "Unknown option `stemming`");
+    assert_eq!(SearchOptions::from_overrides("slop = two").unwrap_err(), "Invalid `slop`: two");
+  }
+
+  #[test]
+  fn overrides_reject_weights_out_of_range() {
+    let error = |name: &str, value: &str| SearchOptions::default().apply_override(name, value).unwrap_err();
+
+    assert_eq!(error("limit", "0"), "`limit` must be between 1 and 1000");
+    assert_eq!(error("limit", "-1"), "Invalid `limit`: -1");
+    assert_eq!(error("freshness", "1.5"), "`freshness` must be between 0 and 1");
+    assert_eq!(error("freshness", "NaN"), "`freshness` must be between 0 and 1");
+    assert_eq!(error("half_life_days", "0"), "`half_life_days` must be positive");
+    assert_eq!(error("half_life_days", "NaN"), "`half_life_days` must be positive");
+    assert_eq!(error("authority", "-1"), "`authority` must not be negative");
+    assert_eq!(error("clicks", "-0.1"), "`clicks` must be between 0 and 1");
+    assert_eq!(error("phrase_boost", "-2"), "`phrase_boost` must not be negative");
+    assert_eq!(error("boost", "title:-5"), "Invalid boost `-5` for title");
+    assert_eq!(error("site_boost", "docs.rs:NaN"), "Invalid boost `NaN` for docs.rs");
+  }
+
+  #[test]
+  fn options_set_directly_are_validated() {
+    let options = SearchOptions { authority_weight: Score::NAN, ..SearchOptions::default() };
+    assert_eq!(options.validate().unwrap_err(), "`authority` must not be negative");
+
+    let mut options = SearchOptions::default();
+    options.site_boosts.insert("docs.rs".to_string(), -1.0);
+    assert_eq!(options.validate().unwrap_err(), "Boost of `docs.rs` must not be negative");
+    assert!(SearchOptions::default().validate().is_ok());
+  }
+}
@@ -0,0 +1,185 @@
+use std::{collections::HashMap, path::Path};
+
+use config::{SYNONYMS, SYNONYMS_PATH};
+
+// Alternatives a query term was expanded into
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryExpansion {
+  pub term: String,
+  pub alternatives: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SynonymDictionary {
+  entries: HashMap<String, Vec<String>>,
+  // Number of words in the longest term, bounds the multi word lookups
+  max_words: usize,
+}
+
+// Characters a bare query word can be made of. Anything else ( quotes, `field:`, `+`/`-` operators, .. )
+// is query syntax and left untouched
+fn is_plain_word(word: &str) -> bool {
+  !word.starts_with(['+', '-'])
+    && word.chars().all(|c| c.is_alphanumeric() || "-_.#+".contains(c))
+}
+
+fn quote_if_needed(text: &str) -> String {
+  if text.contains(char::is_whitespace) || !is_plain_word(text) {
+    format!("\"{}\"", text.replace('"', ""))
+  } else {
+    text.to_string()
+  }
+}
+
+impl SynonymDictionary {
+  // Built-in `config::SYNONYMS`, extended by the entries of `config::SYNONYMS_PATH` in `data_dir` when the
+  // file exists
+  pub fn from_config(data_dir: &Path) -> Self {
+    let mut dictionary = Self::default();
+    for (term, alternatives) in SYNONYMS {
+      dictionary.insert(term, alternatives.iter().map(|alternative| alternative.to_string()).collect());
+    }
+
+    if let Ok(contents) = std::fs::read_to_string(data_dir.join(SYNONYMS_PATH)) {
+      for (term, alternatives) in Self::parse(&contents) {
+        dictionary.insert(&term, alternatives);
+      }
+    }
+
+    dictionary
+  }
+
+  // Parses a synonyms file. One `term = alternative, alternative` entry per line, lines starting with
+  // `#` are comments, so terms like `c#` can have synonyms
+  pub fn parse(contents: &str) -> Vec<(String, Vec<String>)> {
+    contents.lines()
+      .map(str::trim)
+      .filter(|line| !line.starts_with('#'))
+      .filter_map(|line| line.split_once('='))
+      .map(|(term, alternatives)| (
+        term.trim().to_string(),
+        alternatives.split(',')
+          .map(|alternative| alternative.trim().to_string())
+          .filter(|alternative| !alternative.is_empty())
+          .collect::<Vec<_>>(),
+      ))
+      .filter(|(term, alternatives)| !term.is_empty() && !alternatives.is_empty())
+      .collect()
+  }
+
+  pub fn insert(&mut self, term: &str, alternatives: Vec<String>) {
+    let term = term.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    self.max_words = self.max_words.max(term.split(' ').count());
+    self.entries.insert(term, alternatives);
+  }
+
+  pub fn get(&self, term: &str) -> Option<&Vec<String>> {
+    self.entries.get(&term.to_lowercase())
+  }
+
+  // Rewrites the query text so that every known term also matches its alternatives with a lower weight,
+  // e.g. `k8s pods` -> `(k8s OR kubernetes^0.5) pods`. Quoted phrases and query syntax are not expanded
+  pub fn expand(&self, text: &str, weight: f32) -> (String, Vec<QueryExpansion>) {
+    let mut words: Vec<String> = Vec::new();
+    let mut in_quotes = false;
+    for word in text.split_whitespace() {
+      match words.last_mut() {
+        Some(last) if in_quotes => {
+          last.push(' ');
+          last.push_str(word);
+        },
+        _ => words.push(word.to_string()),
+      }
+      if word.matches('"').count() % 2 == 1 {
+        in_quotes = !in_quotes;
+      }
+    }
+
+    let mut rewritten = Vec::new();
+    let mut expansions = Vec::new();
+    let mut idx = 0;
+    while idx < words.len() {
+      let matched = (1..=self.max_words.min(words.len() - idx)).rev().find_map(|num_words| {
+        let window = &words[idx..idx + num_words];
+        if !window.iter().all(|word| is_plain_word(word)) {
+          return None;
+        }
+        let term = window.join(" ");
+        self.get(&term).map(|alternatives| (num_words, term, alternatives))
+      });
+
+      let Some((num_words, term, alternatives)) = matched else {
+        rewritten.push(words[idx].clone());
+        idx += 1;
+        continue;
+      };
+
+      let original = if num_words > 1 { format!("({})", term) } else { term.clone() };
+      let boosted_alternatives = alternatives.iter()
+        .map(|alternative| format!("{}^{}", quote_if_needed(alternative), weight));
+      rewritten.push(format!(
+        "({})",
+        std::iter::once(original).chain(boosted_alternatives).collect::<Vec<_>>().join(" OR ")
+      ));
+      expansions.push(QueryExpansion { term, alternatives: alternatives.clone() });
+      idx += num_words;
+    }
+
+    (rewritten.join(" "), expansions)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn terms_can_contain_hashes() {
+    let entries = SynonymDictionary::parse("# Languages\n  # f# = fsharp\nc# = csharp, c sharp\nk8s = kubernetes,\nempty =\n");
+
+    assert_eq!(entries, vec![("c#".to_string(), vec!["csharp".to_string(), "c sharp".to_string()]), ("k8s".to_string(), vec!["kubernetes".to_string()])]);
+  }
+
+  fn dictionary() -> SynonymDictionary {
+    let mut dictionary = SynonymDictionary::default();
+    for (term, alternatives) in SynonymDictionary::parse("k8s = kubernetes\nHash  Map = hashmap\nc++ = cpp, c plus plus\njs = javascript") {
+      dictionary.insert(&term, alternatives);
+    }
+    dictionary
+  }
+
+  #[test]
+  fn known_terms_are_expanded_with_weighted_alternatives() {
+    let dictionary = dictionary();
+    for (query, expanded) in [
+      ("k8s pods", "(k8s OR kubernetes^0.5) pods"),
+      ("K8S", "(K8S OR kubernetes^0.5)"),
+      // The longest term wins, its alternatives are quoted when they are several words
+      ("rust hash map iteration", "rust ((hash map) OR hashmap^0.5) iteration"),
+      ("c++ templates", "(c++ OR cpp^0.5 OR \"c plus plus\"^0.5) templates"),
+      ("k8s AND js OR go", "(k8s OR kubernetes^0.5) AND (js OR javascript^0.5) OR go"),
+      ("pods", "pods"),
+    ] {
+      assert_eq!(dictionary.expand(query, 0.5).0, expanded, "{}", query);
+    }
+
+    let (_, expansions) = dictionary.expand("hash map in js", 0.5);
+    assert_eq!(expansions, vec![
+      QueryExpansion { term: "hash map".to_string(), alternatives: vec!["hashmap".to_string()] },
+      QueryExpansion { term: "js".to_string(), alternatives: vec!["javascript".to_string()] },
+    ]);
+  }
+
+  #[test]
+  fn query_syntax_is_not_expanded() {
+    let dictionary = dictionary();
+    for query in ["+k8s -js", "title:k8s", "k8s^2", "\"k8s pods\"", "\"rust hash\" map", "(k8s)", "hash:map"] {
+      let (expanded, expansions) = dictionary.expand(query, 0.5);
+      assert_eq!(expanded, query);
+      assert!(expansions.is_empty(), "{}", query);
+    }
+
+    // Only the words outside of the phrase
+    assert_eq!(dictionary.expand("\"k8s pods\" js", 0.5).0, "\"k8s pods\" (js OR javascript^0.5)");
+  }
+}
@@ -0,0 +1,46 @@
+use std::cmp::Ordering;
+
+// Orders documentation versions, oldest first. Numbered versions are compared component wise
+// ( `3.12` > `3.8` > `3` ). Aliases of the current release ( `stable`, `latest`, `current` ) sort
+// after every numbered version, other names ( `dev`, `master`, `nightly`, .. ) before them
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+  version_rank(a).cmp(&version_rank(b))
+}
+
+fn version_rank(version: &str) -> (u8, Vec<u64>) {
+  let numbers: Vec<u64> = version
+    .split(|c: char| !c.is_ascii_digit())
+    .filter(|part| !part.is_empty())
+    .filter_map(|part| part.parse().ok())
+    .collect();
+
+  if !numbers.is_empty() {
+    return (1, numbers);
+  }
+
+  match version.to_lowercase().as_str() {
+    "stable" | "latest" | "current" => (2, vec![]),
+    _ => (0, vec![]),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn numbered_versions_are_compared_component_wise() {
+    assert_eq!(compare_versions("3.12", "3.8"), Ordering::Greater);
+    assert_eq!(compare_versions("3", "3.8"), Ordering::Less);
+    assert_eq!(compare_versions("v1.70.0", "1.70.0"), Ordering::Equal);
+    assert_eq!(compare_versions("10.0", "9.9.9"), Ordering::Greater);
+  }
+
+  #[test]
+  fn stable_aliases_are_newer_than_numbered_versions_and_other_names_older() {
+    assert_eq!(compare_versions("stable", "99.0"), Ordering::Greater);
+    assert_eq!(compare_versions("Latest", "current"), Ordering::Equal);
+    assert_eq!(compare_versions("nightly", "1.0"), Ordering::Less);
+    assert_eq!(compare_versions("dev", "stable"), Ordering::Less);
+  }
+}